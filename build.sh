@@ -1,8 +1,13 @@
-rm -rf ./esp
+rm -rf ./esp ./russet.img
+cd ./tools || exit 1
+cargo build || exit 1
+cd .. || exit 1
+if [ ! -f ./keys/developer.key ]; then
+  mkdir -p ./keys
+  ./tools/target/debug/mkrelf keygen ./keys/developer.key ./keys/developer.pub || exit 1
+fi
 cd ./os || exit 1
 cargo build --target x86_64-unknown-uefi || exit 1
-cd ../tools || exit 1
-cargo build || exit 1
 cd .. || exit 1
 ./tools/target/debug/mkrimg || exit 1
-#qemu-system-x86_64 -drive if=pflash,format=raw,readonly=on,file=./firmware/OVMF_CODE.fd -drive if=pflash,format=raw,readonly=on,file=./firmware/OVMF_VARS.fd -drive format=raw,file=fat:rw:./esp
+#qemu-system-x86_64 -drive if=pflash,format=raw,readonly=on,file=./firmware/OVMF_CODE.fd -drive if=pflash,format=raw,readonly=on,file=./firmware/OVMF_VARS.fd -drive format=raw,file=./russet.img
@@ -0,0 +1,37 @@
+use std::env;
+use std::fmt::Write;
+use std::fs;
+use std::path::PathBuf;
+
+fn main() {
+    println!("cargo:rerun-if-env-changed=RUSSET_TRUSTED_KEYS");
+    println!("cargo:rerun-if-env-changed=RUSSET_SIGNATURE_POLICY");
+
+    let paths: Vec<PathBuf> = match env::var_os("RUSSET_TRUSTED_KEYS") {
+        Some(paths) => env::split_paths(&paths).collect(),
+        None => vec![PathBuf::from(env::var("CARGO_MANIFEST_DIR").unwrap()).join("../../../keys/developer.pub")]
+    };
+
+    let mut keys = String::new();
+
+    for path in paths {
+        println!("cargo:rerun-if-changed={}", path.display());
+
+        match fs::read(&path) {
+            Ok(key) if key.len() == 32 => writeln!(keys, "    {key:?},").unwrap(),
+            Ok(_) => panic!("{} is not a valid Ed25519 public key", path.display()),
+            Err(_) => println!("cargo:warning=Trusted key {} not found, system images will be rejected", path.display())
+        }
+    }
+
+    let policy = match env::var("RUSSET_SIGNATURE_POLICY").as_deref() {
+        Err(_) | Ok("enforce") => "Enforce",
+        Ok("permissive") => "Permissive",
+        Ok("disabled") => "Disabled",
+        Ok(policy) => panic!("RUSSET_SIGNATURE_POLICY must be enforce, permissive or disabled, not {policy}")
+    };
+
+    let out = PathBuf::from(env::var("OUT_DIR").unwrap());
+    fs::write(out.join("trusted_keys.rs"), format!("pub const TRUSTED_KEYS: &[[u8; 32]] = &[\n{keys}];\n")).unwrap();
+    fs::write(out.join("signature_policy.rs"), format!("pub const BUILD_SIGNATURE_POLICY: SignaturePolicy = SignaturePolicy::{policy};\n")).unwrap();
+}
@@ -0,0 +1,459 @@
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use crate::parser::Command;
+use crate::serial::SerialMode;
+use crate::timeline::Timeline;
+use crate::{CoreServices, ElfContext, SignaturePolicy, COMMAND_LINE_VARIABLE, DEFAULT_INIT, DEFAULT_KERNEL, DEFAULT_SHELL};
+
+pub const BOOT_CONFIG: &str = "\\rootfs\\System\\BootConfig";
+pub const LAST_ENTRY_VARIABLE: &str = "Russet.LastBootEntry";
+pub const SLOT_VARIABLE: &str = "Russet.BootSlot";
+pub const GOOD_SLOT_VARIABLE: &str = "Russet.LastGoodSlot";
+pub const ATTEMPTS_VARIABLE: &str = "Russet.BootAttempts";
+pub const DEFAULT_TIMEOUT: u32 = 5;
+pub const DEFAULT_ATTEMPTS: u8 = 3;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Slot {
+    A,
+    B
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SlotBoot {
+    pub slot: Slot,
+    pub failed: Option<Slot>
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BootEntry {
+    pub id: String,
+    pub name: String,
+    pub kernel: String,
+    pub arguments: String,
+    pub slots: bool,
+    pub efi: bool
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BootConfig {
+    pub timeout: u32,
+    pub default: Option<String>,
+    pub remember: bool,
+    pub attempts: u8,
+    pub splash: bool,
+    pub serial: Option<SerialMode>,
+    pub entries: Vec<BootEntry>
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KernelOptions {
+    pub init: String,
+    pub shell: String,
+    pub slot: Option<Slot>,
+    pub verbose: bool,
+    pub safe: bool,
+    pub native: bool,
+    pub journal: bool,
+    pub timing: bool
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BootInfo {
+    pub os_string: String,
+    pub bootloader: String,
+    pub kernel: String,
+    pub command_line: String,
+    pub options: KernelOptions,
+    pub policy: SignaturePolicy,
+    pub network: Option<[u8; 4]>,
+    pub timeline: Option<Timeline>,
+    pub firmware_vendor: String,
+    pub firmware_revision: u32,
+    pub uefi_revision: String
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BootConfigErrorKind {
+    Syntax,
+    UnknownKey(String),
+    InvalidValue(String),
+    DuplicateEntry(String),
+    MissingKernel(String),
+    UnknownDefault(String),
+    NoEntries
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BootConfigError {
+    pub line: usize,
+    pub kind: BootConfigErrorKind
+}
+
+impl BootEntry {
+    fn new(id: &str) -> Self {
+        Self { id: id.to_string(), name: id.to_string(), kernel: String::new(), arguments: String::new(), slots: false, efi: false }
+    }
+}
+
+impl Default for BootConfig {
+    fn default() -> Self {
+        Self {
+            timeout: 0,
+            default: None,
+            remember: false,
+            attempts: DEFAULT_ATTEMPTS,
+            splash: false,
+            serial: None,
+            entries: alloc::vec![BootEntry {
+                kernel: String::from(DEFAULT_KERNEL),
+                ..BootEntry::new("Russet")
+            }]
+        }
+    }
+}
+
+impl Default for KernelOptions {
+    fn default() -> Self {
+        Self { init: String::from(DEFAULT_INIT), shell: String::from(DEFAULT_SHELL), slot: None, verbose: false, safe: false, native: false, journal: false, timing: false }
+    }
+}
+
+impl From<&Command> for KernelOptions {
+    fn from(command: &Command) -> Self {
+        let safe = command.has("safe");
+        let slot = command.value("slot").and_then(Slot::parse);
+        let init = slot.unwrap_or(Slot::A).path(DEFAULT_INIT);
+        let path = |name, default: &str| match command.value(name) {
+            Some(path) if !safe && path.starts_with('/') => path.to_string(),
+            _ => default.to_string()
+        };
+
+        Self {
+            init: path("init", &init),
+            shell: path("shell", DEFAULT_SHELL),
+            slot,
+            verbose: command.has("verbose") || command.has("v"),
+            safe,
+            native: !safe && command.has("native"),
+            journal: command.has("journal"),
+            timing: command.has("timing")
+        }
+    }
+}
+
+impl Slot {
+    pub fn parse(name: &str) -> Option<Self> {
+        match name {
+            "A" | "a" => Some(Slot::A),
+            "B" | "b" => Some(Slot::B),
+            _ => None
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Slot::A => "A",
+            Slot::B => "B"
+        }
+    }
+
+    pub fn other(self) -> Self {
+        match self {
+            Slot::A => Slot::B,
+            Slot::B => Slot::A
+        }
+    }
+
+    pub fn path(self, path: &str) -> String {
+        match self {
+            Slot::A => path.to_string(),
+            Slot::B => format!("{path}.B")
+        }
+    }
+}
+
+impl CoreServices {
+    pub fn boot_info(&self) -> BootInfo {
+        let string = |name| self.get_shared_variable(name)
+            .map(|(data, _)| String::from_utf8_lossy(&data).into_owned())
+            .unwrap_or_default();
+
+        BootInfo {
+            os_string: string("Russet.OSString"),
+            bootloader: string("Russet.Bootloader"),
+            kernel: string("Russet.Version"),
+            command_line: string(COMMAND_LINE_VARIABLE),
+            options: self.command_line().as_ref().map(KernelOptions::from).unwrap_or_default(),
+            policy: self.signature_policy(ElfContext::User),
+            network: self.network_server(),
+            timeline: self.boot_timeline(),
+            firmware_vendor: self.firmware_vendor(),
+            firmware_revision: self.firmware_revision(),
+            uefi_revision: self.uefi_revision()
+        }
+    }
+
+    fn slot_variable(&self, name: &str) -> Option<Slot> {
+        let (data, _) = self.get_shared_variable(name).ok()?;
+        Slot::parse(core::str::from_utf8(&data).ok()?)
+    }
+
+    pub fn begin_slot_boot(&self, max_attempts: u8) -> SlotBoot {
+        let active = self.slot_variable(SLOT_VARIABLE).unwrap_or(Slot::A);
+        let mut attempts = self.get_shared_variable(ATTEMPTS_VARIABLE).ok()
+            .and_then(|(data, _)| data.first().copied())
+            .unwrap_or(0);
+        let mut boot = SlotBoot { slot: active, failed: None };
+
+        if attempts >= max_attempts {
+            boot.slot = self.slot_variable(GOOD_SLOT_VARIABLE)
+                .filter(|slot| *slot != active)
+                .unwrap_or(active.other());
+            boot.failed = Some(active);
+            attempts = 0;
+            let _ = self.set_persistent_variable(SLOT_VARIABLE, boot.slot.name().as_bytes());
+        }
+
+        let _ = self.set_persistent_variable(ATTEMPTS_VARIABLE, &[attempts.saturating_add(1)]);
+        boot
+    }
+
+    pub fn fail_slot_boot(&self, max_attempts: u8) {
+        let _ = self.set_persistent_variable(ATTEMPTS_VARIABLE, &[max_attempts]);
+    }
+
+    pub fn mark_boot_successful(&self, slot: Slot) {
+        let _ = self.set_persistent_variable(ATTEMPTS_VARIABLE, &[0]);
+        if self.slot_variable(GOOD_SLOT_VARIABLE) != Some(slot) {
+            let _ = self.set_persistent_variable(GOOD_SLOT_VARIABLE, slot.name().as_bytes());
+        }
+    }
+}
+
+fn parse_bool(value: &str) -> Option<bool> {
+    match value {
+        "yes" | "true" | "on" => Some(true),
+        "no" | "false" | "off" => Some(false),
+        _ => None
+    }
+}
+
+impl BootConfig {
+    pub fn parse(text: &str) -> Result<Self, BootConfigError> {
+        let mut config = Self { timeout: DEFAULT_TIMEOUT, default: None, remember: true, attempts: DEFAULT_ATTEMPTS, splash: true, serial: None, entries: Vec::new() };
+        let mut default_line = 0;
+
+        for (i, line) in text.lines().enumerate() {
+            let line_number = i + 1;
+            let error = |kind| BootConfigError { line: line_number, kind };
+            let line = line.trim();
+
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            if let Some(id) = line.strip_prefix('[').and_then(|line| line.strip_suffix(']')) {
+                let id = id.trim();
+                if id.is_empty() {
+                    return Err(error(BootConfigErrorKind::Syntax));
+                }
+                if config.entries.iter().any(|entry| entry.id == id) {
+                    return Err(error(BootConfigErrorKind::DuplicateEntry(id.to_string())));
+                }
+                if let Some(entry) = config.entries.last() {
+                    if entry.kernel.is_empty() {
+                        return Err(error(BootConfigErrorKind::MissingKernel(entry.id.clone())));
+                    }
+                }
+                config.entries.push(BootEntry::new(id));
+                continue;
+            }
+
+            let Some((key, value)) = line.split_once('=') else {
+                return Err(error(BootConfigErrorKind::Syntax));
+            };
+            let (key, value) = (key.trim(), value.trim());
+
+            match (config.entries.last_mut(), key) {
+                (None, "timeout") => config.timeout = value.parse()
+                    .map_err(|_| error(BootConfigErrorKind::InvalidValue(key.to_string())))?,
+                (None, "default") => {
+                    config.default = Some(value.to_string());
+                    default_line = line_number;
+                },
+                (None, "remember") => config.remember = parse_bool(value)
+                    .ok_or(error(BootConfigErrorKind::InvalidValue(key.to_string())))?,
+                (None, "attempts") => config.attempts = value.parse().ok().filter(|attempts| *attempts > 0)
+                    .ok_or(error(BootConfigErrorKind::InvalidValue(key.to_string())))?,
+                (None, "splash") => config.splash = parse_bool(value)
+                    .ok_or(error(BootConfigErrorKind::InvalidValue(key.to_string())))?,
+                (None, "serial") => config.serial = SerialMode::parse(value)
+                    .ok_or(error(BootConfigErrorKind::InvalidValue(key.to_string())))?,
+                (Some(entry), "name") => entry.name = value.to_string(),
+                (Some(entry), "kernel") => {
+                    entry.kernel = value.to_string();
+                    entry.efi = false;
+                },
+                (Some(entry), "efi") => {
+                    entry.kernel = value.to_string();
+                    entry.efi = true;
+                },
+                (Some(entry), "arguments") => entry.arguments = value.to_string(),
+                (Some(entry), "slots") => entry.slots = parse_bool(value)
+                    .ok_or(error(BootConfigErrorKind::InvalidValue(key.to_string())))?,
+                _ => return Err(error(BootConfigErrorKind::UnknownKey(key.to_string())))
+            }
+        }
+
+        let last_line = text.lines().count();
+        match config.entries.last() {
+            None => return Err(BootConfigError { line: last_line, kind: BootConfigErrorKind::NoEntries }),
+            Some(entry) if entry.kernel.is_empty() => {
+                return Err(BootConfigError { line: last_line, kind: BootConfigErrorKind::MissingKernel(entry.id.clone()) });
+            },
+            _ => ()
+        }
+
+        if let Some(default) = &config.default {
+            if config.find(default).is_none() {
+                return Err(BootConfigError { line: default_line, kind: BootConfigErrorKind::UnknownDefault(default.clone()) });
+            }
+        }
+
+        Ok(config)
+    }
+
+    pub fn find(&self, id: &str) -> Option<usize> {
+        self.entries.iter().position(|entry| entry.id == id)
+    }
+
+    pub fn default_entry(&self, last: Option<&str>) -> usize {
+        last.filter(|_| self.remember)
+            .and_then(|id| self.find(id))
+            .or_else(|| self.default.as_deref().and_then(|id| self.find(id)))
+            .unwrap_or(0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CONFIG: &str = "# Boot menu\n\
+        timeout = 3\n\
+        default = safe\n\
+        remember = no\n\
+        \n\
+        [russet]\n\
+        name = Russet\n\
+        kernel = /System/Kernel\n\
+        slots = yes\n\
+        \n\
+        [safe]\n\
+        kernel = /System/Kernel\n\
+        arguments = --safe\n\
+        \n\
+        [shell]\n\
+        efi = fs0:/EFI/Shell.efi\n";
+
+    fn options(line: &str) -> KernelOptions {
+        KernelOptions::from(&Command::build(line).unwrap())
+    }
+
+    fn error(text: &str) -> BootConfigError {
+        BootConfig::parse(text).unwrap_err()
+    }
+
+    #[test]
+    fn parses_global_keys_and_entries() {
+        let config = BootConfig::parse(CONFIG).unwrap();
+        assert_eq!(config.timeout, 3);
+        assert_eq!(config.default.as_deref(), Some("safe"));
+        assert!(!config.remember);
+        assert!(config.splash);
+        assert_eq!(config.attempts, DEFAULT_ATTEMPTS);
+
+        let ids: Vec<&str> = config.entries.iter().map(|entry| entry.id.as_str()).collect();
+        assert_eq!(ids, ["russet", "safe", "shell"]);
+        assert_eq!(config.entries[0].name, "Russet");
+        assert!(config.entries[0].slots);
+        assert_eq!(config.entries[1].name, "safe");
+        assert_eq!(config.entries[1].arguments, "--safe");
+        assert!(config.entries[2].efi);
+        assert_eq!(config.entries[2].kernel, "fs0:/EFI/Shell.efi");
+    }
+
+    #[test]
+    fn reports_syntax_errors_with_line() {
+        assert_eq!(error("[]\nkernel = /System/Kernel"), BootConfigError { line: 1, kind: BootConfigErrorKind::Syntax });
+        assert_eq!(error("[a]\nkernel /System/Kernel"), BootConfigError { line: 2, kind: BootConfigErrorKind::Syntax });
+        assert_eq!(error("colour = red\n[a]\nkernel = /System/Kernel").kind, BootConfigErrorKind::UnknownKey("colour".into()));
+        assert_eq!(error("[a]\nkernel = /System/Kernel\ntimeout = 1").kind, BootConfigErrorKind::UnknownKey("timeout".into()));
+        assert_eq!(error("timeout = soon\n[a]\nkernel = /System/Kernel").kind, BootConfigErrorKind::InvalidValue("timeout".into()));
+        assert_eq!(error("attempts = 0\n[a]\nkernel = /System/Kernel").kind, BootConfigErrorKind::InvalidValue("attempts".into()));
+        assert_eq!(error("splash = maybe\n[a]\nkernel = /System/Kernel").kind, BootConfigErrorKind::InvalidValue("splash".into()));
+    }
+
+    #[test]
+    fn rejects_duplicate_entries() {
+        let text = "[a]\nkernel = /System/Kernel\n[a]\nkernel = /System/Kernel";
+        assert_eq!(error(text), BootConfigError { line: 3, kind: BootConfigErrorKind::DuplicateEntry("a".into()) });
+    }
+
+    #[test]
+    fn rejects_entries_without_kernel() {
+        assert_eq!(error("[a]\nname = A\n[b]\nkernel = /System/Kernel"),
+            BootConfigError { line: 3, kind: BootConfigErrorKind::MissingKernel("a".into()) });
+        assert_eq!(error("[a]\nkernel = /System/Kernel\n[b]\nname = B"),
+            BootConfigError { line: 4, kind: BootConfigErrorKind::MissingKernel("b".into()) });
+        assert_eq!(error("timeout = 1\n").kind, BootConfigErrorKind::NoEntries);
+        assert_eq!(error("").kind, BootConfigErrorKind::NoEntries);
+    }
+
+    #[test]
+    fn rejects_unknown_default() {
+        let text = "timeout = 1\ndefault = b\n[a]\nkernel = /System/Kernel";
+        assert_eq!(error(text), BootConfigError { line: 2, kind: BootConfigErrorKind::UnknownDefault("b".into()) });
+    }
+
+    #[test]
+    fn default_entry_prefers_remembered_entry() {
+        let mut config = BootConfig::parse(CONFIG).unwrap();
+        assert_eq!(config.default_entry(Some("shell")), 1);
+        assert_eq!(config.default_entry(None), 1);
+
+        config.remember = true;
+        assert_eq!(config.default_entry(Some("shell")), 2);
+        assert_eq!(config.default_entry(Some("removed")), 1);
+
+        config.default = None;
+        assert_eq!(config.default_entry(Some("removed")), 0);
+        assert_eq!(BootConfig::default().default_entry(Some("Russet")), 0);
+    }
+
+    #[test]
+    fn kernel_options_from_command_line() {
+        assert_eq!(options("/System/Kernel"), KernelOptions::default());
+
+        let parsed = options("/System/Kernel --init=/Custom/Init --shell=/Custom/Shell -v --native --journal --timing");
+        assert_eq!(parsed.init, "/Custom/Init");
+        assert_eq!(parsed.shell, "/Custom/Shell");
+        assert!(parsed.verbose && parsed.native && parsed.journal && parsed.timing);
+        assert!(!parsed.safe);
+
+        let parsed = options("/System/Kernel --init=Relative/Init --slot=b");
+        assert_eq!(parsed.slot, Some(Slot::B));
+        assert_eq!(parsed.init, "/System/Init.B");
+    }
+
+    #[test]
+    fn safe_mode_ignores_custom_paths() {
+        let parsed = options("/System/Kernel --safe --init=/Custom/Init --shell=/Custom/Shell --native");
+        assert!(parsed.safe);
+        assert!(!parsed.native);
+        assert_eq!(parsed.init, DEFAULT_INIT);
+        assert_eq!(parsed.shell, DEFAULT_SHELL);
+    }
+}
@@ -0,0 +1,135 @@
+use alloc::string::String;
+use alloc::vec;
+use alloc::vec::Vec;
+use uefi::prelude::*;
+use uefi::{CStr16, CString16};
+use uefi::proto::device_path::DevicePath;
+use uefi::proto::device_path::build::{self, DevicePathBuilder};
+use uefi::proto::loaded_image::LoadedImage;
+use uefi::proto::media::fs::SimpleFileSystem;
+use uefi::table::boot::{LoadImageSource, OpenProtocolAttributes, OpenProtocolParams};
+use crate::{CoreServices, ExecBinaryError};
+use crate::network::is_network_path;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EfiPath {
+    pub volume: Option<usize>,
+    pub path: String
+}
+
+impl EfiPath {
+    pub fn parse(path: &str) -> Option<Self> {
+        let (volume, path) = match path.split_once(':') {
+            Some((volume, path)) => {
+                let index = volume.get(..2)
+                    .filter(|prefix| prefix.eq_ignore_ascii_case("fs"))
+                    .and_then(|_| volume[2..].parse().ok())?;
+                (Some(index), path)
+            },
+            None => (None, path)
+        };
+
+        let path = path.trim().replace('/', "\\");
+        match path.trim_start_matches('\\') {
+            "" => None,
+            path => Some(Self { volume, path: alloc::format!("\\{path}") })
+        }
+    }
+}
+
+pub fn is_efi_path(path: &str) -> bool {
+    !is_network_path(path) && (path.contains(':') || path.to_ascii_lowercase().ends_with(".efi"))
+}
+
+impl CoreServices {
+    pub fn volumes(&self) -> Vec<Handle> {
+        self.system_table.boot_services().find_handles::<SimpleFileSystem>().unwrap_or_default()
+    }
+
+    pub fn boot_volume(&self) -> Option<Handle> {
+        let boot_services = self.system_table.boot_services();
+        boot_services.open_protocol_exclusive::<LoadedImage>(boot_services.image_handle()).ok()?.device()
+    }
+
+    pub fn chainload(&self, path: &str, options: &str) -> Result<(), ExecBinaryError> {
+        let boot_services = self.system_table.boot_services();
+        let path = EfiPath::parse(path).ok_or(ExecBinaryError::NotFound)?;
+        let volume = match path.volume {
+            Some(index) => self.volumes().get(index).copied(),
+            None => self.boot_volume()
+        }.ok_or(ExecBinaryError::NotFound)?;
+
+        let volume_path = unsafe {
+            boot_services.open_protocol::<DevicePath>(OpenProtocolParams {
+                handle: volume,
+                agent: boot_services.image_handle(),
+                controller: None
+            }, OpenProtocolAttributes::GetProtocol)
+        }.map_err(|_| ExecBinaryError::NotFound)?;
+
+        let mut buf = vec![0; path.path.len() + 1];
+        let file = CStr16::from_str_with_buf(&path.path, &mut buf).map_err(|_| ExecBinaryError::NotFound)?;
+        let mut storage = Vec::new();
+        let mut builder = DevicePathBuilder::with_vec(&mut storage);
+
+        for node in volume_path.node_iter() {
+            builder = builder.push(&node).map_err(|_| ExecBinaryError::Unsupported)?;
+        }
+
+        let device_path = builder.push(&build::media::FilePath { path_name: file })
+            .and_then(|builder| builder.finalize())
+            .map_err(|_| ExecBinaryError::Unsupported)?;
+
+        let handle = boot_services.load_image(boot_services.image_handle(), LoadImageSource::FromDevicePath {
+            device_path,
+            from_boot_manager: false
+        }).map_err(|e| match e.status() {
+            Status::NOT_FOUND => ExecBinaryError::NotFound,
+            Status::UNSUPPORTED | Status::LOAD_ERROR => ExecBinaryError::Unsupported,
+            _ => ExecBinaryError::Load(e)
+        })?;
+
+        let options = CString16::try_from(options.trim()).unwrap_or_default();
+        if !options.is_empty() {
+            if let Ok(mut image) = boot_services.open_protocol_exclusive::<LoadedImage>(handle) {
+                unsafe { image.set_load_options(options.as_ptr().cast(), options.num_bytes() as u32) };
+            }
+        }
+
+        match boot_services.start_image(handle) {
+            Ok(_) => Err(ExecBinaryError::Finished),
+            Err(e) => Err(ExecBinaryError::Runtime(e))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_volume_and_path() {
+        assert_eq!(EfiPath::parse("fs1:/EFI/Boot/BOOTX64.EFI"), Some(EfiPath { volume: Some(1), path: "\\EFI\\Boot\\BOOTX64.EFI".into() }));
+        assert_eq!(EfiPath::parse("FS0:\\Shell.efi"), Some(EfiPath { volume: Some(0), path: "\\Shell.efi".into() }));
+        assert_eq!(EfiPath::parse("/EFI/Shell.efi"), Some(EfiPath { volume: None, path: "\\EFI\\Shell.efi".into() }));
+        assert_eq!(EfiPath::parse("Shell.efi"), Some(EfiPath { volume: None, path: "\\Shell.efi".into() }));
+    }
+
+    #[test]
+    fn rejects_invalid_paths() {
+        assert_eq!(EfiPath::parse("fs0:"), None);
+        assert_eq!(EfiPath::parse("fs0:/"), None);
+        assert_eq!(EfiPath::parse("fs:/Shell.efi"), None);
+        assert_eq!(EfiPath::parse("hd0:/Shell.efi"), None);
+        assert_eq!(EfiPath::parse("f:/Shell.efi"), None);
+        assert_eq!(EfiPath::parse(""), None);
+    }
+
+    #[test]
+    fn recognises_efi_paths() {
+        assert!(is_efi_path("fs0:/EFI/Shell.efi"));
+        assert!(is_efi_path("/EFI/Shell.EFI"));
+        assert!(!is_efi_path("/System/Kernel"));
+        assert!(!is_efi_path("tftp://10.0.2.2/Shell.efi"));
+    }
+}
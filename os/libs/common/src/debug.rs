@@ -0,0 +1,267 @@
+use alloc::boxed::Box;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::arch::asm;
+use core::fmt::{self, Write};
+use core::ptr;
+use core::sync::atomic::{AtomicU16, Ordering};
+use relf::SymbolTable;
+use uefi::{cstr16, CStr16, Handle};
+use uefi::proto::loaded_image::LoadedImage;
+use uefi::table::{Boot, SystemTable};
+use uefi::table::cfg::{SMBIOS3_GUID, SMBIOS_GUID};
+use uefi::table::runtime::VariableAttributes;
+use uefi_raw::protocol::loaded_image::LoadedImageProtocol;
+use crate::VENDOR;
+
+const SYMBOLS_VARIABLE: &CStr16 = cstr16!("Russet.Symbols");
+const RECORD_SIZE: usize = 32;
+const MAX_FRAMES: usize = 32;
+const DEBUG_EXIT_OPTION: &[u8] = b"russet.debug-exit=";
+const SMBIOS_OEM_STRINGS: u8 = 11;
+const SMBIOS_END: u8 = 127;
+
+static mut SAVED_IMAGES: Option<Vec<Image>> = None;
+static DEBUG_EXIT_PORT: AtomicU16 = AtomicU16::new(0);
+
+#[derive(Clone, Copy)]
+pub(crate) struct Image {
+    base: usize,
+    size: usize,
+    table: *mut u8,
+    table_len: usize
+}
+
+impl Image {
+    fn contains(&self, address: usize) -> bool {
+        address >= self.base && address - self.base < self.size
+    }
+
+    unsafe fn symbols(&self) -> Option<SymbolTable<'static>> {
+        SymbolTable::parse(core::slice::from_raw_parts(self.table, self.table_len)).ok()
+    }
+}
+
+pub(crate) fn read_images(st: &SystemTable<Boot>) -> Vec<Image> {
+    let mut buf = vec![0u8; RECORD_SIZE * 64];
+
+    match st.runtime_services().get_variable(SYMBOLS_VARIABLE, &VENDOR, &mut buf) {
+        Ok((data, _)) => data.chunks_exact(RECORD_SIZE).map(|record| {
+            let field = |i: usize| u64::from_le_bytes(record[i * 8..i * 8 + 8].try_into().unwrap()) as usize;
+            Image { base: field(0), size: field(1), table: field(2) as *mut u8, table_len: field(3) }
+        }).collect(),
+        Err(_) => Vec::new()
+    }
+}
+
+fn write_images(st: &SystemTable<Boot>, images: &[Image]) {
+    let mut data = Vec::with_capacity(images.len() * RECORD_SIZE);
+
+    for image in images {
+        for field in [image.base, image.size, image.table as usize, image.table_len] {
+            data.extend_from_slice(&(field as u64).to_le_bytes());
+        }
+    }
+
+    let _ = if data.is_empty() {
+        st.runtime_services().delete_variable(SYMBOLS_VARIABLE, &VENDOR)
+    } else {
+        st.runtime_services().set_variable(SYMBOLS_VARIABLE, &VENDOR, VariableAttributes::BOOTSERVICE_ACCESS, &data)
+    };
+}
+
+pub(crate) fn register_symbols(st: &SystemTable<Boot>, handle: Handle, symbols: Vec<u8>) -> Option<usize> {
+    let (base, size) = {
+        let loaded_image = st.boot_services().open_protocol_exclusive::<LoadedImage>(handle).ok()?;
+        let raw = unsafe { &*(&*loaded_image as *const LoadedImage as *const LoadedImageProtocol) };
+        (raw.image_base as usize, raw.image_size as usize)
+    };
+
+    let table_len = symbols.len();
+    let table = Box::into_raw(symbols.into_boxed_slice()) as *mut u8;
+
+    let mut images = read_images(st);
+    images.push(Image { base, size, table, table_len });
+    write_images(st, &images);
+
+    Some(base)
+}
+
+pub(crate) fn unregister_symbols(st: &SystemTable<Boot>, base: usize) {
+    let mut images = read_images(st);
+
+    images.retain(|image| {
+        if image.base == base {
+            unsafe { drop(Box::from_raw(ptr::slice_from_raw_parts_mut(image.table, image.table_len))) };
+            false
+        } else {
+            true
+        }
+    });
+
+    write_images(st, &images);
+}
+
+pub(crate) struct Console;
+
+impl Write for Console {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        print!("{s}");
+        Ok(())
+    }
+}
+
+pub(crate) fn save_images(st: &SystemTable<Boot>) {
+    unsafe { SAVED_IMAGES = Some(read_images(st)) };
+}
+
+pub(crate) unsafe fn print_backtrace(st: &SystemTable<Boot>) {
+    write_backtrace(&read_images(st), &mut Console, None);
+}
+
+#[inline(always)]
+#[allow(static_mut_refs)]
+pub(crate) unsafe fn write_saved_backtrace(out: &mut impl Write, fault: Option<(usize, usize)>) {
+    write_backtrace(SAVED_IMAGES.as_deref().unwrap_or_default(), out, fault);
+}
+
+unsafe fn write_address(images: &[Image], out: &mut impl Write, address: usize) -> bool {
+    let Some(image) = images.iter().find(|image| image.contains(address)) else {
+        let _ = writeln!(out, "  {address:#018x}  <unknown>");
+        return false;
+    };
+
+    let rva = (address - image.base) as u32;
+    let _ = match image.symbols().and_then(|symbols| symbols.lookup(rva)) {
+        Some(symbol) => writeln!(out, "  {address:#018x}  {}+{:#x}", symbol.name, rva - symbol.rva),
+        None => writeln!(out, "  {address:#018x}  <unknown>")
+    };
+    true
+}
+
+#[inline(always)]
+pub(crate) unsafe fn write_backtrace(images: &[Image], out: &mut impl Write, fault: Option<(usize, usize)>) {
+    let mut frame: *const usize = match fault {
+        Some((_, rbp)) => rbp as *const usize,
+        None => {
+            let rbp: *const usize;
+            asm!("mov {}, rbp", out(reg) rbp);
+            rbp
+        }
+    };
+
+    let _ = writeln!(out, "\nStack trace:");
+
+    if let Some((rip, _)) = fault {
+        write_address(images, out, rip);
+    }
+
+    for _ in 0..MAX_FRAMES {
+        if frame.is_null() || !(frame as usize).is_multiple_of(8) {
+            break;
+        }
+
+        if !write_address(images, out, *frame.add(1)) {
+            break;
+        }
+
+        let next = *frame as *const usize;
+        if next <= frame {
+            break;
+        }
+        frame = next;
+    }
+}
+
+fn oem_strings(table: &[u8]) -> impl Iterator<Item = &[u8]> {
+    let mut rest = table;
+    core::iter::from_fn(move || loop {
+        let (&kind, &length) = (rest.first()?, rest.get(1)?);
+        let strings = rest.get(length as usize..)?;
+        let end = strings.windows(2).position(|pair| pair == [0, 0])?;
+        let (structure, next) = (strings[..end].split(|&byte| byte == 0), &strings[end + 2..]);
+
+        rest = if kind == SMBIOS_END { &[] } else { next };
+        if kind == SMBIOS_OEM_STRINGS && length >= 5 {
+            return Some(structure);
+        }
+    }).flatten()
+}
+
+fn parse_port(value: &[u8]) -> Option<u16> {
+    let value = core::str::from_utf8(value).ok()?;
+    match value.strip_prefix("0x") {
+        Some(hex) => u16::from_str_radix(hex, 16).ok(),
+        None => value.parse().ok()
+    }
+}
+
+pub(crate) fn debug_exit_port(table: &[u8]) -> Option<u16> {
+    oem_strings(table).find_map(|string| parse_port(string.strip_prefix(DEBUG_EXIT_OPTION)?))
+}
+
+unsafe fn smbios_table(st: &SystemTable<Boot>) -> Option<&'static [u8]> {
+    let entries = st.config_table();
+    if let Some(entry) = entries.iter().find(|entry| entry.guid == SMBIOS3_GUID) {
+        let anchor = entry.address as *const u8;
+        let size = ptr::read_unaligned(anchor.add(0x0c) as *const u32) as usize;
+        let address = ptr::read_unaligned(anchor.add(0x10) as *const u64) as usize;
+        return Some(core::slice::from_raw_parts(address as *const u8, size));
+    }
+
+    let entry = entries.iter().find(|entry| entry.guid == SMBIOS_GUID)?;
+    let anchor = entry.address as *const u8;
+    let size = ptr::read_unaligned(anchor.add(0x16) as *const u16) as usize;
+    let address = ptr::read_unaligned(anchor.add(0x18) as *const u32) as usize;
+    Some(core::slice::from_raw_parts(address as *const u8, size))
+}
+
+pub(crate) fn detect_debug_exit(st: &SystemTable<Boot>) {
+    let port = unsafe { smbios_table(st) }.and_then(debug_exit_port).unwrap_or(0);
+    DEBUG_EXIT_PORT.store(port, Ordering::Release);
+}
+
+pub(crate) unsafe fn debug_exit(status: u8) {
+    let port = DEBUG_EXIT_PORT.load(Ordering::Acquire);
+    if port != 0 {
+        asm!("out dx, al", in("dx") port, in("al") status.saturating_add(1), options(nomem, nostack, preserves_flags));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn structure(kind: u8, formatted: &[u8], strings: &[&str]) -> Vec<u8> {
+        let mut bytes = vec![kind, 4 + formatted.len() as u8, 0, 0];
+        bytes.extend_from_slice(formatted);
+        for string in strings {
+            bytes.extend_from_slice(string.as_bytes());
+            bytes.push(0);
+        }
+        if strings.is_empty() {
+            bytes.push(0);
+        }
+        bytes.push(0);
+        bytes
+    }
+
+    #[test]
+    fn debug_exit_port_from_oem_strings() {
+        let mut table = structure(1, &[1, 2, 3], &["QEMU", "Standard PC"]);
+        table.extend(structure(SMBIOS_OEM_STRINGS, &[2], &["vendor=1", "russet.debug-exit=0xf4"]));
+        table.extend(structure(SMBIOS_END, &[], &[]));
+        assert_eq!(debug_exit_port(&table), Some(0xf4));
+    }
+
+    #[test]
+    fn debug_exit_port_requires_oem_string() {
+        let mut table = structure(1, &[], &["russet.debug-exit=0xf4"]);
+        table.extend(structure(SMBIOS_OEM_STRINGS, &[1], &["russet.debug-exit=nope"]));
+        table.extend(structure(SMBIOS_END, &[], &[]));
+        table.extend(structure(SMBIOS_OEM_STRINGS, &[1], &["russet.debug-exit=0xf4"]));
+        assert_eq!(debug_exit_port(&table), None);
+        assert_eq!(debug_exit_port(&table[..6]), None);
+        assert_eq!(debug_exit_port(&[]), None);
+    }
+}
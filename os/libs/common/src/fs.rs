@@ -20,7 +20,7 @@ impl From<SystemTable<Boot>> for CoreFileSystem {
 }
 
 impl CoreFileSystem {
-    fn get_fs(&self) -> FileSystem {
+    fn get_fs(&self) -> FileSystem<'_> {
         let handle = self.system_table.boot_services().image_handle();
         let fs = self.system_table.boot_services().get_image_file_system(handle).expect("Failed to start up filesystem");
         FileSystem::new(fs)
@@ -77,9 +77,12 @@ impl CoreFileSystem {
         self.get_fs().try_exists(Path::new(&CStr16::from_str_with_buf(path, &mut buf).unwrap())).unwrap_or(false)
     }
 
-    pub fn mkdir(&mut self, path: &str) {
+    pub fn mkdir(&self, path: &str) -> bool {
         let mut buf: Vec<u16> = vec![0; path.len() + 1];
-        self.get_fs().create_dir_all(Path::new(&CStr16::from_str_with_buf(path, &mut buf).unwrap())).unwrap();
+        match CStr16::from_str_with_buf(path, &mut buf) {
+            Ok(path) => self.get_fs().create_dir_all(Path::new(&path)).is_ok(),
+            Err(_) => false
+        }
     }
 
     pub fn get_real_cwd(&self) -> String {
@@ -142,9 +145,22 @@ impl CoreFileSystem {
             return None;
         }
         let mut buf: Vec<u16> = vec![0; path.len() + 1];
-        match self.get_fs().read_to_string(Path::new(&CStr16::from_str_with_buf(path, &mut buf).unwrap())) {
-            Ok(b) => Some(b),
-            Err(_) => None
+        self.get_fs().read_to_string(Path::new(&CStr16::from_str_with_buf(path, &mut buf).unwrap())).ok()
+    }
+
+    pub fn read_bytes(&self, path: &str) -> Option<Vec<u8>> {
+        if path.is_empty() {
+            return None;
+        }
+        let mut buf: Vec<u16> = vec![0; path.len() + 1];
+        self.get_fs().read(Path::new(&CStr16::from_str_with_buf(path, &mut buf).ok()?)).ok()
+    }
+
+    pub fn write_bytes(&self, path: &str, data: &[u8]) -> bool {
+        let mut buf: Vec<u16> = vec![0; path.len() + 1];
+        match CStr16::from_str_with_buf(path, &mut buf) {
+            Ok(path) if !path.is_empty() => self.get_fs().write(Path::new(&path), data).is_ok(),
+            _ => false
         }
     }
 
@@ -164,14 +180,33 @@ impl CoreFileSystem {
         }
     }
 
-    pub fn rmdir(&mut self, path: &str) {
+    pub fn list_dir(&self, path: &str) -> Option<Vec<(String, bool)>> {
         let mut buf: Vec<u16> = vec![0; path.len() + 1];
-        self.get_fs().remove_dir(Path::new(&CStr16::from_str_with_buf(path, &mut buf).unwrap())).unwrap();
+        let entries = self.get_fs().read_dir(Path::new(&CStr16::from_str_with_buf(path, &mut buf).ok()?)).ok()?;
+
+        let mut entries: Vec<(String, bool)> = entries
+            .filter_map(|entry| entry.ok())
+            .map(|info| (info.file_name().to_string(), info.is_directory()))
+            .filter(|(name, _)| name != "." && name != "..")
+            .collect();
+        entries.sort();
+        Some(entries)
     }
 
-    pub fn unlink(&mut self, path: &str) {
+    pub fn rmdir(&self, path: &str) -> bool {
         let mut buf: Vec<u16> = vec![0; path.len() + 1];
-        self.get_fs().remove_file(Path::new(&CStr16::from_str_with_buf(path, &mut buf).unwrap())).unwrap();
+        match CStr16::from_str_with_buf(path, &mut buf) {
+            Ok(path) => self.get_fs().remove_dir(Path::new(&path)).is_ok(),
+            Err(_) => false
+        }
+    }
+
+    pub fn unlink(&self, path: &str) -> bool {
+        let mut buf: Vec<u16> = vec![0; path.len() + 1];
+        match CStr16::from_str_with_buf(path, &mut buf) {
+            Ok(path) => self.get_fs().remove_file(Path::new(&path)).is_ok(),
+            Err(_) => false
+        }
     }
 
     pub fn recursive_rmdir(&mut self, path: &str) {
@@ -0,0 +1,197 @@
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt::{self, Display, Formatter, Write};
+use core::mem::size_of;
+use core::ptr;
+use core::sync::atomic::{AtomicPtr, Ordering};
+use log::{Level, LevelFilter, Log, Metadata, Record};
+use uefi::table::boot::{AllocateType, MemoryType};
+use crate::CoreServices;
+use crate::memory::PAGE_SIZE;
+use crate::timeline::timestamp;
+
+pub const JOURNAL_VARIABLE: &str = "Russet.Journal";
+pub const JOURNAL_DIRECTORY: &str = "\\rootfs\\System\\Logs";
+pub const JOURNAL_FILE: &str = "\\rootfs\\System\\Logs\\Journal";
+pub const PREVIOUS_JOURNAL_FILE: &str = "\\rootfs\\System\\Logs\\Journal.1";
+
+const MAGIC: u32 = u32::from_le_bytes(*b"RLOG");
+const SLOTS: usize = 512;
+const SOURCE_SIZE: usize = 24;
+const TEXT_SIZE: usize = 220;
+
+static RING: AtomicPtr<Ring> = AtomicPtr::new(ptr::null_mut());
+static JOURNAL: Journal = Journal;
+
+#[repr(C)]
+struct Slot {
+    tsc: u64,
+    level: u8,
+    source_len: u8,
+    text_len: u16,
+    source: [u8; SOURCE_SIZE],
+    text: [u8; TEXT_SIZE]
+}
+
+#[repr(C)]
+struct Ring {
+    magic: u32,
+    frequency: u64,
+    next: u64,
+    records: [Slot; SLOTS]
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Entry {
+    pub sequence: u64,
+    pub micros: u64,
+    pub level: Level,
+    pub source: String,
+    pub text: String
+}
+
+struct Journal;
+
+struct Truncated<'a> {
+    buf: &'a mut [u8],
+    len: usize
+}
+
+impl Write for Truncated<'_> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        let mut end = s.len().min(self.buf.len() - self.len);
+        while !s.is_char_boundary(end) {
+            end -= 1;
+        }
+
+        self.buf[self.len..self.len + end].copy_from_slice(&s.as_bytes()[..end]);
+        self.len += end;
+        Ok(())
+    }
+}
+
+fn level_from(value: u8) -> Level {
+    match value {
+        1 => Level::Error,
+        2 => Level::Warn,
+        3 => Level::Info,
+        4 => Level::Debug,
+        _ => Level::Trace
+    }
+}
+
+impl Log for Journal {
+    fn enabled(&self, _: &Metadata) -> bool {
+        !RING.load(Ordering::Acquire).is_null()
+    }
+
+    fn log(&self, record: &Record) {
+        let ring = RING.load(Ordering::Acquire);
+        if ring.is_null() {
+            return;
+        }
+
+        let ring = unsafe { &mut *ring };
+        let slot = &mut ring.records[(ring.next % SLOTS as u64) as usize];
+        let mut source = Truncated { buf: &mut slot.source, len: 0 };
+        let _ = source.write_str(record.target());
+        let source_len = source.len;
+        let mut text = Truncated { buf: &mut slot.text, len: 0 };
+        let _ = text.write_fmt(*record.args());
+        let text_len = text.len;
+
+        slot.tsc = timestamp();
+        slot.level = record.level() as u8;
+        slot.source_len = source_len as u8;
+        slot.text_len = text_len as u16;
+        ring.next += 1;
+    }
+
+    fn flush(&self) {}
+}
+
+impl Display for Entry {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "[{:>5}.{:06}] {:<5} {}: {}", self.micros / 1_000_000, self.micros % 1_000_000, self.level, self.source, self.text)
+    }
+}
+
+fn attach(ring: *mut Ring) {
+    RING.store(ring, Ordering::Release);
+    let _ = log::set_logger(&JOURNAL);
+    log::set_max_level(LevelFilter::Trace);
+}
+
+pub fn entries() -> Vec<Entry> {
+    let ring = RING.load(Ordering::Acquire);
+    if ring.is_null() {
+        return Vec::new();
+    }
+
+    let ring = unsafe { &*ring };
+    let micros = |tsc: u64| match ring.frequency {
+        0 => 0,
+        frequency => (tsc as u128 * 1_000_000 / frequency as u128) as u64
+    };
+
+    (ring.next.saturating_sub(SLOTS as u64)..ring.next).map(|sequence| {
+        let slot = &ring.records[(sequence % SLOTS as u64) as usize];
+        Entry {
+            sequence,
+            micros: micros(slot.tsc),
+            level: level_from(slot.level),
+            source: String::from_utf8_lossy(&slot.source[..slot.source_len as usize]).into_owned(),
+            text: String::from_utf8_lossy(&slot.text[..slot.text_len as usize]).into_owned()
+        }
+    }).collect()
+}
+
+impl CoreServices {
+    pub(crate) fn attach_journal(&self) {
+        let Ok((data, _)) = self.get_shared_variable(JOURNAL_VARIABLE) else {
+            return;
+        };
+
+        if let Ok(address) = data.try_into().map(u64::from_le_bytes) {
+            let ring = address as *mut Ring;
+            if unsafe { (*ring).magic } == MAGIC {
+                attach(ring);
+            }
+        }
+    }
+
+    pub fn begin_journal(&self) -> bool {
+        let pages = size_of::<Ring>().div_ceil(PAGE_SIZE as usize);
+        let Ok(address) = self.system_table.boot_services().allocate_pages(AllocateType::AnyPages, MemoryType::LOADER_DATA, pages) else {
+            return false;
+        };
+
+        let ring = address as *mut Ring;
+        unsafe {
+            ptr::write_bytes(ring as *mut u8, 0, size_of::<Ring>());
+            (*ring).magic = MAGIC;
+            (*ring).frequency = self.boot_timeline().map(|timeline| timeline.frequency).unwrap_or(0);
+        }
+
+        if self.set_shared_variable(JOURNAL_VARIABLE, &address.to_le_bytes()).is_err() {
+            return false;
+        }
+
+        attach(ring);
+        true
+    }
+
+    pub fn save_journal(&mut self) -> bool {
+        if !self.fs.file_exists(JOURNAL_DIRECTORY) && !self.fs.mkdir(JOURNAL_DIRECTORY) {
+            return false;
+        }
+
+        if let Some(previous) = self.fs.read_bytes(JOURNAL_FILE) {
+            self.fs.write_bytes(PREVIOUS_JOURNAL_FILE, &previous);
+        }
+
+        let text: String = entries().iter().map(|entry| format!("{entry}\n")).collect();
+        self.fs.write_bytes(JOURNAL_FILE, text.as_bytes())
+    }
+}
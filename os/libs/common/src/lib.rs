@@ -6,47 +6,130 @@ use alloc::string::{String, ToString};
 use alloc::vec::Vec;
 use uefi::proto::console::text::{Color, Key};
 use uefi::prelude::*;
-use uefi::{guid, print, CStr16, CString16, Char16, Error, Guid};
+use uefi::{guid, CStr16, CString16, Char16, Error, Guid};
 use uefi::fs::{FileSystemResult, IoError};
 use uefi::fs::Error::Io;
 use uefi::proto::device_path::LoadedImageDevicePath;
+use uefi::proto::loaded_image::LoadedImage;
 use uefi::proto::media::fs::SimpleFileSystem;
-use uefi::table::boot::{LoadImageSource, ScopedProtocol};
-use uefi::table::runtime::{VariableAttributes, VariableVendor};
+use uefi::table::boot::{EventType, LoadImageSource, MemoryMap, MemoryType, OpenProtocolAttributes, OpenProtocolParams, ScopedProtocol, TimerTrigger, Tpl};
+use uefi::table::Runtime;
+use uefi::table::runtime::{ResetType, VariableAttributes, VariableVendor};
 use crate::fs::CoreFileSystem;
+use crate::network::BinarySource;
+use crate::parser::Command;
+use relf::Bundle;
 
-use core::panic::PanicInfo;
-use elf::{ElfBytes, ParseError};
-use elf::endian::AnyEndian;
-use elf::note::Note;
+use core::fmt::{Display, Formatter, Write};
+use core::panic::{Location, PanicInfo};
+use core::sync::atomic::{AtomicBool, Ordering};
 use uefi::proto::console::text::Output;
-use uefi::println;
 
+#[macro_export]
+macro_rules! print {
+    ($($arg:tt)*) => ($crate::_print(core::format_args!($($arg)*)));
+}
+
+#[macro_export]
+macro_rules! println {
+    () => ($crate::print!("\n"));
+    ($($arg:tt)*) => ($crate::_print(core::format_args!("{}{}", core::format_args!($($arg)*), "\n")));
+}
+
+pub mod boot;
+pub mod chainload;
+pub mod journal;
+pub mod memory;
+pub mod network;
 pub mod parser;
+pub mod serial;
+pub mod splash;
+pub mod sysinfo;
+pub mod timeline;
+mod debug;
 mod fs;
 
+pub use relf::Context as ElfContext;
+pub use relf::{Codec, Header as ElfHeader, Metadata, SUPPORTED_ABI};
+
+include!(concat!(env!("OUT_DIR"), "/trusted_keys.rs"));
+include!(concat!(env!("OUT_DIR"), "/signature_policy.rs"));
+
 static mut SYSTEM_TABLE: Option<SystemTable<Boot>> = None;
 static mut HANDLE: Option<Handle> = None;
 static mut BUILD_INFO: Option<String> = None;
 static mut FATAL_PANIC: bool = false;
+static BOOT_SERVICES_EXITED: AtomicBool = AtomicBool::new(false);
 
 pub const GUID: Guid = guid!("cf3dd8e5-823e-4d06-8caf-d0fd9e49f588");
 pub const VENDOR: VariableVendor = VariableVendor(GUID);
 pub const OS_VERSION: &str = "0.1";
-pub const SUPPORTED_ABI: [u32; 1] = [2];
 pub const DEFAULT_SHELL: &str = "/System/Programs/CommandInterpreter";
 pub const DEFAULT_KERNEL: &str = "/System/Kernel";
+pub const DEFAULT_INIT: &str = "/System/Init";
+pub const COMMAND_LINE_VARIABLE: &str = "Russet.CommandLine";
+pub const STOP_EXIT_STATUS: u8 = 0x7e;
+
+const SERIAL_POLL_INTERVAL: u64 = 100_000;
+const POLICY_OPTION: &[u8] = b"Russet.SignaturePolicy=";
+
+pub struct Completion {
+    pub line: String,
+    pub candidates: Vec<String>
+}
+
+pub struct BundleReport {
+    pub header: ElfHeader,
+    pub checksum: Result<(), ElfError>,
+    pub signature: Option<Result<(), ElfError>>,
+    pub metadata: Option<Metadata>
+}
 
 pub struct CoreServices {
     system_table: SystemTable<Boot>,
+    policy: SignaturePolicy,
     pub fs: CoreFileSystem
 }
 
-#[panic_handler]
-#[allow(unused_must_use, static_mut_refs)]
+enum StopOutput {
+    Console,
+    Serial(serial::SerialPort)
+}
+
+impl Write for StopOutput {
+    fn write_str(&mut self, s: &str) -> core::fmt::Result {
+        match self {
+            StopOutput::Console => debug::Console.write_str(s),
+            StopOutput::Serial(port) => port.write_str(s)
+        }
+    }
+}
+
+struct StopLocation<'a>(&'a Location<'a>);
+
+impl Display for StopLocation<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        for c in self.0.file().chars() {
+            f.write_char(if c == '\\' { '/' } else { c })?;
+        }
+        write!(f, ":{}:{}", self.0.line(), self.0.column())
+    }
+}
+
+#[doc(hidden)]
+pub fn _print(args: core::fmt::Arguments) {
+    uefi::helpers::_print(args);
+    serial::mirror(args);
+}
+
+#[cfg_attr(not(test), panic_handler)]
+#[allow(unused_must_use, static_mut_refs, dead_code, clippy::empty_loop)]
 unsafe fn panic(info: &PanicInfo) -> ! {
-    if !FATAL_PANIC {
+    if !FATAL_PANIC && !BOOT_SERVICES_EXITED.load(Ordering::Acquire) {
         println!("{}", info);
+        if let Some(ref st) = &SYSTEM_TABLE {
+            debug::print_backtrace(st);
+        }
         if let (Some(ref mut st), Some(ref mut h)) = (&mut SYSTEM_TABLE, &mut HANDLE) {
             let mut return_data = Char16::try_from(' ').unwrap();
             st.boot_services().exit(*h, Status::ABORTED, 0, &mut return_data);
@@ -54,37 +137,74 @@ unsafe fn panic(info: &PanicInfo) -> ! {
         loop {}
     }
 
-    if let Some(ref mut st) = &mut SYSTEM_TABLE {
-        let stdout: &mut Output = st.stdout();
-        stdout.set_color(Color::White, Color::Red);
-        stdout.enable_cursor(false);
-        stdout.clear();
+    stop(&StopLocation(info.location().unwrap()), &info.message(), None)
+}
+
+#[allow(clippy::missing_safety_doc, unused_must_use, static_mut_refs)]
+pub unsafe fn stop(code: &dyn Display, message: &dyn Display, fault: Option<(usize, usize)>) -> ! {
+    let exited = BOOT_SERVICES_EXITED.load(Ordering::Acquire);
+    let mut out = if exited {
+        StopOutput::Serial(serial::SerialPort::new(serial::COM1))
+    } else {
+        StopOutput::Console
+    };
+
+    match &mut SYSTEM_TABLE {
+        Some(ref mut st) if !exited => {
+            let stdout: &mut Output = st.stdout();
+            stdout.set_color(Color::White, Color::Red);
+            stdout.enable_cursor(false);
+            stdout.clear();
+        },
+        _ => {
+            writeln!(out);
+        }
     }
 
-    println!("*** STOP: {}", info.location().unwrap().to_string().replace("\\", "/"));
-    println!("{}", info.message());
+    writeln!(out, "*** STOP: {}", code);
+    writeln!(out, "{}", message);
+
+    match &SYSTEM_TABLE {
+        Some(ref st) if !exited => debug::write_backtrace(&debug::read_images(st), &mut out, fault),
+        _ => debug::write_saved_backtrace(&mut out, fault)
+    }
 
-    if let Some(ref mut build) = &mut BUILD_INFO {
-        println!("\n{}", build);
+    if let Some(ref build) = &BUILD_INFO {
+        writeln!(out, "\n{}", build);
     }
 
     if let Some(ref mut st) = &mut SYSTEM_TABLE {
-        println!("\nFirmware: {} {}", st.firmware_vendor(), st.firmware_revision());
-        println!("Specification: {}", st.uefi_revision());
+        writeln!(out, "\nFirmware: {} {}", st.firmware_vendor(), st.firmware_revision());
+        writeln!(out, "Specification: {}", st.uefi_revision());
     }
 
-    println!("\nPlease restart the system.");
-    loop {}
+    writeln!(out, "\nPlease restart the system.");
+    debug::debug_exit(STOP_EXIT_STATUS);
+    loop {
+        core::arch::asm!("cli; hlt");
+    }
 }
 
 impl CoreServices {
     #[allow(clippy::missing_safety_doc)]
-    pub unsafe fn init(value: SystemTable<Boot>, panic: bool) -> Self {
+    pub unsafe fn init(mut value: SystemTable<Boot>, panic: bool) -> Self {
+        uefi::allocator::init(&mut value);
+        debug::detect_debug_exit(&value);
         FATAL_PANIC = panic;
-        Self {
+        let core = Self {
             fs: CoreFileSystem::from(value.unsafe_clone()),
+            policy: inherited_policy(&value),
             system_table: value,
+        };
+
+        if let Ok((data, _)) = core.get_shared_variable(serial::SERIAL_VARIABLE) {
+            if let [mode] = data[..] {
+                serial::set_mode(serial::SerialMode::from(mode));
+            }
         }
+
+        core.attach_journal();
+        core
     }
 
     #[allow(clippy::missing_safety_doc)]
@@ -99,12 +219,23 @@ impl CoreServices {
         }
     }
 
+    pub fn exit_boot_services(self) -> (SystemTable<Runtime>, MemoryMap<'static>) {
+        debug::save_images(&self.system_table);
+        unsafe {
+            SYSTEM_TABLE = None;
+            HANDLE = None;
+        }
+
+        BOOT_SERVICES_EXITED.store(true, Ordering::Release);
+        self.system_table.exit_boot_services(MemoryType::LOADER_DATA)
+    }
+
     #[allow(clippy::missing_safety_doc)]
     pub unsafe fn get_system_table(&self) -> SystemTable<Boot> {
         self.system_table.unsafe_clone()
     }
 
-    pub fn set_shared_variable(&mut self, name: &str, value: &[u8]) -> uefi::Result {
+    pub fn set_shared_variable(&self, name: &str, value: &[u8]) -> uefi::Result {
         let mut buf = vec![0; name.len() + 1];
 
         self.system_table.runtime_services().set_variable(
@@ -115,7 +246,18 @@ impl CoreServices {
         )
     }
 
-    pub fn get_shared_variable(&mut self, name: &str) -> Result<(Vec<u8>, VariableAttributes), Error> {
+    pub fn set_persistent_variable(&self, name: &str, value: &[u8]) -> uefi::Result {
+        let mut buf = vec![0; name.len() + 1];
+
+        self.system_table.runtime_services().set_variable(
+            CStr16::from_str_with_buf(name, &mut buf).unwrap_or(cstr16!("")),
+            &VENDOR,
+            VariableAttributes::NON_VOLATILE | VariableAttributes::BOOTSERVICE_ACCESS,
+            value
+        )
+    }
+
+    pub fn get_shared_variable(&self, name: &str) -> Result<(Vec<u8>, VariableAttributes), Error> {
         let mut buf1 = vec![0; name.len() + 1];
         let mut buf2 = [0u8; 65536];
 
@@ -137,44 +279,94 @@ impl CoreServices {
         )
     }
 
+    pub fn command_line(&self) -> Option<Command> {
+        let (data, _) = self.get_shared_variable(COMMAND_LINE_VARIABLE).ok()?;
+        Command::build(&String::from_utf8(data).ok()?).ok()
+    }
+
     pub fn set_color(&mut self, fg: Color, bg: Color) -> uefi::Result<()> {
         self.system_table.stdout().set_color(fg, bg)
     }
 
+    pub fn wait_for_key(&mut self) -> Option<Key> {
+        let key_event = self.system_table.stdin().wait_for_key_event()?;
+        if serial::mode() != serial::SerialMode::Full {
+            let mut events = [key_event];
+            self.system_table.boot_services().wait_for_event(&mut events).discard_errdata().ok()?;
+            return self.system_table.stdin().read_key().ok().flatten();
+        }
+
+        let timer = unsafe { self.system_table.boot_services().create_event(EventType::TIMER, Tpl::APPLICATION, None, None) }.ok()?;
+        let _ = self.system_table.boot_services().set_timer(&timer, TimerTrigger::Periodic(SERIAL_POLL_INTERVAL));
+
+        let key = loop {
+            if let Some(key) = serial::read_key() {
+                break Some(key);
+            }
+
+            let mut events = unsafe { [key_event.unsafe_clone(), timer.unsafe_clone()] };
+            if let Ok(0) = self.system_table.boot_services().wait_for_event(&mut events) {
+                break self.system_table.stdin().read_key().ok().flatten();
+            }
+        };
+
+        let _ = self.system_table.boot_services().close_event(timer);
+        key
+    }
+
     pub fn readline(&mut self) -> String {
-        let system_table = &mut self.system_table;
+        self.edit_line("")
+    }
+
+    pub fn edit_line(&mut self, initial: &str) -> String {
+        self.edit_line_with("", initial, |_, _| None)
+    }
 
-        let mut out: String = String::from("");
-        let mut chars: u32 = 0;
+    pub fn edit_line_with<F>(&mut self, prompt: &str, initial: &str, mut complete: F) -> String
+        where F: FnMut(&CoreServices, &str) -> Option<Completion> {
+        let mut out: String = String::from(initial);
+        let mut chars: u32 = initial.chars().count() as u32;
+        print!("{prompt}{initial}");
 
         loop {
-            let mut events = [system_table.stdin().wait_for_key_event().unwrap()];
-            system_table.boot_services()
-                .wait_for_event(&mut events)
-                .discard_errdata().expect("Failed to discard errors");
-
             let ret = Char16::try_from('\r').unwrap();
             let bks = Char16::try_from('\x08').unwrap();
             let ctc = Char16::try_from('\u{3}').unwrap();
-            match system_table.stdin().read_key().expect("Failed to read key") {
+            let tab = Char16::try_from('\t').unwrap();
+            match self.wait_for_key() {
                 Some(Key::Printable(key)) if key == ret => {
                     print!("\r\n");
                     return out;
                 }
 
-                Some(Key::Printable(key)) if key == bks => {
-                    if chars > 0 {
-                        chars -= 1;
-                        out = String::from(&out[..out.len() - 1]);
-                        print!("\x08");
-                    }
+                Some(Key::Printable(key)) if key == bks && erase_char(&mut out) => {
+                    chars -= 1;
+                    print!("\x08");
                 }
 
+                Some(Key::Printable(key)) if key == bks => {}
+
                 Some(Key::Printable(key)) if key == ctc => {
                     print!("\r\n");
                     return String::from("");
                 }
 
+                Some(Key::Printable(key)) if key == tab => {
+                    let Some(completion) = complete(self, &out) else {
+                        continue;
+                    };
+
+                    if completion.candidates.len() > 1 {
+                        print!("\r\n{}\r\n{prompt}{out}", completion.candidates.join("  "));
+                    }
+
+                    if completion.line != out {
+                        print!("{}{}", "\x08".repeat(chars as usize), completion.line);
+                        chars = completion.line.chars().count() as u32;
+                        out = completion.line;
+                    }
+                }
+
                 Some(Key::Printable(key)) => {
                     chars += 1;
                     out += &key.to_string();
@@ -186,15 +378,19 @@ impl CoreServices {
         }
     }
 
-    fn get_kernel_binary(&self, path: &str) -> FileSystemResult<Vec<u8>> {
+    fn get_kernel_binary(&self, path: &str) -> FileSystemResult<(Vec<u8>, BinarySource)> {
         let boot_services = self.system_table.boot_services();
         let string = format!("\\rootfs{}", path.replace("/", "\\"));
+        if let Some(data) = self.network_binary(&string) {
+            return Ok((data, BinarySource::Network));
+        }
+
         let mut buf: Vec<u16> = vec![0; string.len() + 1];
         let cstr16 = CStr16::from_str_with_buf(&string, &mut buf).unwrap();
         let path: CString16 = CString16::from(cstr16);
         let fs: ScopedProtocol<SimpleFileSystem> = boot_services.get_image_file_system(boot_services.image_handle()).unwrap();
         let mut fs = uefi::fs::FileSystem::new(fs);
-        fs.read(path.as_ref())
+        fs.read(path.as_ref()).map(|data| (data, BinarySource::Disk))
     }
 
     pub fn execute_kmode_binary(&self, path: &str, strict: bool) -> Result<(), ExecBinaryError> {
@@ -207,14 +403,26 @@ impl CoreServices {
         let binary = self.get_kernel_binary(path);
 
         match binary {
-            Ok(data) => {
-                if let Ok(data) = self.elf_to_pe(data.as_slice(), ElfContext::Kernel) {
-                    match boot_services.load_image(boot_services.image_handle(), LoadImageSource::FromBuffer {
+            Ok((data, source)) => {
+                self.mark(&format!("{path} read"));
+                let symbols = Self::elf_symbols(data.as_slice());
+
+                match self.elf_to_pe(data.as_slice(), ElfContext::Kernel, source) {
+                    Ok(data) => match boot_services.load_image(boot_services.image_handle(), LoadImageSource::FromBuffer {
                         buffer: data.as_slice(),
                         file_path: Some(&**loaded_image)
                     }) {
                         Ok(handle) => {
-                            match boot_services.start_image(handle) {
+                            self.mark("image loaded");
+                            let options = self.policy_options();
+                            self.pass_signature_policy(handle, &options);
+                            let image = symbols.and_then(|symbols| debug::register_symbols(&self.system_table, handle, symbols));
+                            let result = boot_services.start_image(handle);
+                            if let Some(image) = image {
+                                debug::unregister_symbols(&self.system_table, image);
+                            }
+
+                            match result {
                                 Ok(_) => if strict {
                                     panic!("CRITICAL_PROCESS_DIED")
                                 } else {
@@ -244,11 +452,22 @@ impl CoreServices {
                                 _ => Err(ExecBinaryError::Load(e))
                             }
                         }
+                    },
+                    Err(ElfError::Unsigned) => if strict {
+                        panic!("SYSTEM_IMAGE_UNSIGNED")
+                    } else {
+                        Err(ExecBinaryError::Unsigned)
+                    },
+                    Err(ElfError::BadSignature) => if strict {
+                        panic!("SYSTEM_IMAGE_BAD_SIGNATURE")
+                    } else {
+                        Err(ExecBinaryError::BadSignature)
+                    },
+                    Err(_) => if strict {
+                        panic!("BOUND_IMAGE_UNSUPPORTED")
+                    } else {
+                        Err(ExecBinaryError::Unsupported)
                     }
-                } else if strict {
-                    panic!("BOUND_IMAGE_UNSUPPORTED")
-                } else {
-                    Err(ExecBinaryError::Unsupported)
                 }
             },
             Err(e) => {
@@ -282,14 +501,18 @@ impl CoreServices {
         }
     }
 
-    fn get_user_binary(&self, path: &str) -> FileSystemResult<Vec<u8>> {
+    fn get_user_binary(&self, path: &str) -> FileSystemResult<(Vec<u8>, BinarySource)> {
+        if let Some(data) = self.network_binary(path) {
+            return Ok((data, BinarySource::Network));
+        }
+
         let boot_services = self.system_table.boot_services();
         let mut buf: Vec<u16> = vec![0; path.len() + 1];
         let cstr16 = CStr16::from_str_with_buf(path, &mut buf).unwrap();
         let path: CString16 = CString16::from(cstr16);
         let fs: ScopedProtocol<SimpleFileSystem> = boot_services.get_image_file_system(boot_services.image_handle()).unwrap();
         let mut fs = uefi::fs::FileSystem::new(fs);
-        fs.read(path.as_ref())
+        fs.read(path.as_ref()).map(|data| (data, BinarySource::Disk))
     }
 
     pub fn execute_user_binary(&self, path: &str) -> Result<(), ExecBinaryError> {
@@ -301,15 +524,28 @@ impl CoreServices {
 
         let binary = self.get_user_binary(path);
 
-        match binary {
-            Ok(data) => {
-                if let Ok(data) = self.elf_to_pe(data.as_slice(), ElfContext::User) {
-                    match boot_services.load_image(boot_services.image_handle(), LoadImageSource::FromBuffer {
+        let result = match binary {
+            Ok((data, source)) => {
+                self.mark(&format!("{path} read"));
+                let symbols = Self::elf_symbols(data.as_slice());
+
+                match self.elf_to_pe(data.as_slice(), ElfContext::User, source) {
+                    Ok(data) => match boot_services.load_image(boot_services.image_handle(), LoadImageSource::FromBuffer {
                         buffer: data.as_slice(),
                         file_path: Some(&**loaded_image)
                     }) {
                         Ok(handle) => {
-                            match boot_services.start_image(handle) {
+                            self.mark("image loaded");
+                            log::info!("starting {path}");
+                            let options = self.policy_options();
+                            self.pass_signature_policy(handle, &options);
+                            let image = symbols.and_then(|symbols| debug::register_symbols(&self.system_table, handle, symbols));
+                            let result = boot_services.start_image(handle);
+                            if let Some(image) = image {
+                                debug::unregister_symbols(&self.system_table, image);
+                            }
+
+                            match result {
                                 Ok(_) => Err(ExecBinaryError::Finished),
                                 Err(e) => {
                                     match e.status() {
@@ -323,9 +559,10 @@ impl CoreServices {
                             Status::UNSUPPORTED => Err(ExecBinaryError::Unsupported),
                             _ => Err(ExecBinaryError::Load(e))
                         }
-                    }
-                } else {
-                    Err(ExecBinaryError::Unsupported)
+                    },
+                    Err(ElfError::Unsigned) => Err(ExecBinaryError::Unsigned),
+                    Err(ElfError::BadSignature) => Err(ExecBinaryError::BadSignature),
+                    Err(_) => Err(ExecBinaryError::Unsupported)
                 }
             },
             Err(e) => {
@@ -340,7 +577,19 @@ impl CoreServices {
                     _ => Err(ExecBinaryError::ReadFS(e))
                 }
             }
+        };
+
+        match result {
+            Err(ExecBinaryError::Finished) => log::info!("{path} finished"),
+            Err(ref e) => log::warn!("{path} failed: {e:?}"),
+            Ok(_) => ()
         }
+        result
+    }
+
+    pub fn shutdown(&self, status: u8) -> ! {
+        unsafe { debug::debug_exit(status) };
+        self.system_table.runtime_services().reset(ResetType::SHUTDOWN, Status::SUCCESS, None)
     }
 
     pub fn firmware_vendor(&self) -> String {
@@ -355,63 +604,122 @@ impl CoreServices {
         self.system_table.firmware_revision()
     }
 
-    pub fn elf_to_pe(&self, elf: &[u8], expected_context: ElfContext) -> Result<Vec<u8>, ElfError> {
-        let expected_context = expected_context as u32;
-        let file = ElfBytes::<AnyEndian>::minimal_parse(elf)?;
+    pub fn signature_policy(&self, context: ElfContext) -> SignaturePolicy {
+        match context {
+            ElfContext::Kernel => SignaturePolicy::Enforce,
+            ElfContext::User => self.policy
+        }
+    }
 
-        let version_header = file.section_header_by_name(".note.tag")?.ok_or(ElfError::SectionNotFound)?;
-        match file.section_data_as_notes(&version_header)?.next().ok_or(ElfError::SectionNotFound)? {
-            Note::GnuAbiTag(_) => Err(ElfError::InvalidPlatform),
-            Note::GnuBuildId(_) => Err(ElfError::InvalidPlatform),
-            Note::Unknown(version) => {
-                if version.n_type == 1 && version.name == "Russet " {
-                    let data = version.desc;
+    pub fn set_signature_policy(&mut self, policy: SignaturePolicy) {
+        self.policy = policy;
+    }
 
-                    let context_bytes = [data[4], data[5], data[6], data[7]];
-                    let context = u32::from_le_bytes(context_bytes);
+    fn pass_signature_policy(&self, handle: Handle, options: &[u8]) {
+        if let Ok(mut image) = self.system_table.boot_services().open_protocol_exclusive::<LoadedImage>(handle) {
+            unsafe { image.set_load_options(options.as_ptr(), options.len() as u32) };
+        }
+    }
 
-                    let abi_version_bytes = [data[8], data[9], data[10], data[11]];
-                    let abi_version = u32::from_le_bytes(abi_version_bytes);
+    fn policy_options(&self) -> [u8; POLICY_OPTION.len() + 1] {
+        let mut options = [0; POLICY_OPTION.len() + 1];
+        options[..POLICY_OPTION.len()].copy_from_slice(POLICY_OPTION);
+        options[POLICY_OPTION.len()] = self.policy as u8;
+        options
+    }
 
-                    let checksum_bytes = [data[12], data[13], data[14], data[15]];
-                    let checksum = u32::from_le_bytes(checksum_bytes);
+    pub fn elf_to_pe(&self, elf: &[u8], expected_context: ElfContext, source: BinarySource) -> Result<Vec<u8>, ElfError> {
+        let bundle = Bundle::parse(elf)?;
 
-                    let source_header = file.section_header_by_name(".text")?.ok_or(ElfError::SectionNotFound)?;
-                    let data = file.section_data(&source_header)?;
+        if bundle.header.context != expected_context {
+            Err(ElfError::InvalidContext)
+        } else if !SUPPORTED_ABI.contains(&bundle.header.abi_version) {
+            Err(ElfError::UnsupportedABI)
+        } else {
+            self.verify_signature(&bundle, source)?;
+            self.mark("signature checked");
 
-                    let crc: crc::Crc<u32> = crc::Crc::<u32>::new(&crc::CRC_32_BZIP2);
-                    let calculated_checksum = crc.checksum(data.0);
+            let pe = bundle.header.decompress(bundle.payload)?;
+            self.mark("payload decompressed");
 
-                    if context != expected_context {
-                        Err(ElfError::InvalidContext)
-                    } else if !SUPPORTED_ABI.contains(&abi_version) {
-                        Err(ElfError::UnsupportedABI)
-                    } else if calculated_checksum != checksum {
-                        Err(ElfError::Corrupted)
-                    } else {
-                        Ok(Vec::from(data.0))
-                    }
-                } else {
-                    Err(ElfError::InvalidPlatform)
-                }
+            if !bundle.header.verify(&pe) {
+                return Err(ElfError::Corrupted);
             }
+            self.mark("checksum verified");
+
+            Ok(pe)
+        }
+    }
+
+    pub fn inspect_bundle(&self, elf: &[u8]) -> Result<BundleReport, ElfError> {
+        let bundle = Bundle::parse(elf)?;
+        let checksum = bundle.pe().map(|_| ()).map_err(ElfError::from);
+        let signature = bundle.signature.as_ref()
+            .map(|signature| signature.verify(&bundle.header, bundle.payload, TRUSTED_KEYS).map_err(ElfError::from));
+
+        Ok(BundleReport { header: bundle.header, checksum, signature, metadata: bundle.metadata })
+    }
+
+    pub fn read_kernel_bundle(&self, path: &str) -> Option<Vec<u8>> {
+        self.get_kernel_binary(path).ok().map(|(data, _)| data)
+    }
+
+    pub fn elf_metadata(&self, elf: &[u8]) -> Result<Option<Metadata>, ElfError> {
+        Ok(Bundle::parse(elf)?.metadata)
+    }
+
+    pub fn get_program_metadata(&self, path: &str) -> Option<Metadata> {
+        let (data, _) = self.get_user_binary(path).ok()?;
+        self.elf_metadata(&data).ok().flatten()
+    }
+
+    fn elf_symbols(elf: &[u8]) -> Option<Vec<u8>> {
+        Bundle::parse(elf).ok()?.symbols.map(Vec::from)
+    }
+
+    fn verify_signature(&self, bundle: &Bundle, source: BinarySource) -> Result<(), ElfError> {
+        let policy = match source {
+            BinarySource::Network => SignaturePolicy::Enforce,
+            BinarySource::Disk => self.signature_policy(bundle.header.context)
+        };
+
+        match (policy, &bundle.signature) {
+            (SignaturePolicy::Disabled, _) => Ok(()),
+            (_, Some(signature)) => Ok(signature.verify(&bundle.header, bundle.payload, TRUSTED_KEYS)?),
+            (SignaturePolicy::Enforce, None) => Err(ElfError::Unsigned),
+            (_, None) => Ok(())
         }
     }
 }
 
 #[derive(Debug)]
 pub enum ElfError {
-    Parse(ParseError),
+    InvalidElf,
+    Truncated,
+    BadNoteSize,
+    Format(relf::Error),
     SectionNotFound,
     InvalidPlatform,
     InvalidContext,
     UnsupportedABI,
-    Corrupted
+    Corrupted,
+    Unsigned,
+    BadSignature
 }
 
-impl From<ParseError> for ElfError {
-    fn from(value: ParseError) -> Self {
-        Self::Parse(value)
+impl From<relf::Error> for ElfError {
+    fn from(value: relf::Error) -> Self {
+        match value {
+            relf::Error::InvalidElf => Self::InvalidElf,
+            relf::Error::Truncated => Self::Truncated,
+            relf::Error::BadNoteSize => Self::BadNoteSize,
+            relf::Error::SectionNotFound(_) => Self::SectionNotFound,
+            relf::Error::NotRusset => Self::InvalidPlatform,
+            relf::Error::UnknownContext(_) => Self::InvalidContext,
+            relf::Error::Untrusted | relf::Error::BadSignature => Self::BadSignature,
+            relf::Error::Corrupted | relf::Error::Decompress => Self::Corrupted,
+            _ => Self::Format(value)
+        }
     }
 }
 
@@ -472,11 +780,64 @@ pub enum ExecBinaryError {
     Load(Error),
     ReadIO(IoError),
     ReadFS(uefi::fs::Error),
+    Unsigned,
+    BadSignature
 }
 
-#[repr(u32)]
-#[derive(Debug)]
-pub enum ElfContext {
-    Kernel = 1,
-    User = 2
+#[repr(u8)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SignaturePolicy {
+    Disabled = 0,
+    Permissive = 1,
+    Enforce = 2
+}
+
+fn erase_char(line: &mut String) -> bool {
+    line.pop().is_some()
+}
+
+fn inherited_policy(system_table: &SystemTable<Boot>) -> SignaturePolicy {
+    let boot_services = system_table.boot_services();
+    let image = unsafe {
+        boot_services.open_protocol::<LoadedImage>(OpenProtocolParams {
+            handle: boot_services.image_handle(),
+            agent: boot_services.image_handle(),
+            controller: None
+        }, OpenProtocolAttributes::GetProtocol)
+    };
+
+    match image.as_ref().ok().and_then(|image| image.load_options_as_bytes()) {
+        Some([prefix @ .., policy]) if prefix == POLICY_OPTION => SignaturePolicy::from(*policy),
+        _ => SignaturePolicy::Enforce
+    }
+}
+
+impl From<u8> for SignaturePolicy {
+    fn from(value: u8) -> Self {
+        match value {
+            0 => Self::Disabled,
+            2 => Self::Enforce,
+            _ => Self::Permissive
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn erase_char_removes_whole_characters() {
+        let mut line = String::from("Rapport-été");
+        assert!(erase_char(&mut line));
+        assert_eq!(line, "Rapport-ét");
+        assert!(erase_char(&mut line));
+        assert_eq!(line, "Rapport-é");
+
+        let mut line = String::from("日");
+        assert!(erase_char(&mut line));
+        assert!(line.is_empty());
+        assert!(!erase_char(&mut line));
+    }
 }
@@ -0,0 +1,448 @@
+use core::alloc::{GlobalAlloc, Layout};
+use core::arch::asm;
+use core::cell::UnsafeCell;
+use core::mem::size_of;
+use core::ptr;
+use core::sync::atomic::{AtomicBool, Ordering};
+use uefi::allocator::Allocator;
+use uefi::table::boot::{MemoryDescriptor, MemoryMap, MemoryType};
+use crate::BOOT_SERVICES_EXITED;
+
+pub const PAGE_SIZE: u64 = 4096;
+pub const HEAP_PAGES: usize = 1024;
+
+const MIN_HEAP_PAGES: usize = 16;
+const ADDRESS_MASK: u64 = 0x000f_ffff_ffff_f000;
+const PRESENT: u64 = 1;
+const HUGE_PAGE: u64 = 1 << 7;
+const LA57: u64 = 1 << 12;
+
+static KERNEL_HEAP: Locked<Heap> = Locked::new(Heap::empty());
+static HEAP_READY: AtomicBool = AtomicBool::new(false);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MemoryStats {
+    pub total: u64,
+    pub free: u64,
+    pub heap_size: u64,
+    pub heap_used: u64
+}
+
+pub struct FrameAllocator {
+    bitmap: &'static mut [u64],
+    frames: usize,
+    usable: usize,
+    free: usize,
+    next: usize
+}
+
+struct FreeBlock {
+    size: usize,
+    next: *mut FreeBlock
+}
+
+struct Heap {
+    start: usize,
+    size: usize,
+    used: usize,
+    head: *mut FreeBlock
+}
+
+struct Locked<T> {
+    locked: AtomicBool,
+    value: UnsafeCell<T>
+}
+
+pub struct SystemAllocator;
+
+unsafe impl<T> Sync for Locked<T> {}
+
+impl<T> Locked<T> {
+    const fn new(value: T) -> Self {
+        Self { locked: AtomicBool::new(false), value: UnsafeCell::new(value) }
+    }
+
+    fn lock<R>(&self, f: impl FnOnce(&mut T) -> R) -> R {
+        while self.locked.compare_exchange_weak(false, true, Ordering::Acquire, Ordering::Relaxed).is_err() {
+            core::hint::spin_loop();
+        }
+
+        let result = f(unsafe { &mut *self.value.get() });
+        self.locked.store(false, Ordering::Release);
+        result
+    }
+}
+
+fn is_usable(descriptor: &MemoryDescriptor) -> bool {
+    matches!(descriptor.ty, MemoryType::CONVENTIONAL | MemoryType::BOOT_SERVICES_CODE | MemoryType::BOOT_SERVICES_DATA)
+}
+
+fn end_of(descriptor: &MemoryDescriptor) -> u64 {
+    descriptor.phys_start + descriptor.page_count * PAGE_SIZE
+}
+
+fn descriptor_table(store: impl FnOnce(*mut u8)) -> (u64, u64) {
+    let mut table = [0u8; 10];
+    store(table.as_mut_ptr());
+    let limit = u16::from_le_bytes([table[0], table[1]]) as u64;
+    let base = u64::from_le_bytes(table[2..].try_into().unwrap());
+    (base, base + limit + 1)
+}
+
+impl FrameAllocator {
+    pub fn new(map: &MemoryMap) -> Option<Self> {
+        let frames = (map.entries().filter(|d| is_usable(d)).map(end_of).max()? / PAGE_SIZE) as usize;
+        let words = frames.div_ceil(64);
+        let pages = (words * size_of::<u64>()).div_ceil(PAGE_SIZE as usize) as u64;
+
+        let stack: u64;
+        unsafe { asm!("mov {}, rsp", out(reg) stack) };
+        let stack = map.entries().find(|d| (d.phys_start..end_of(d)).contains(&stack));
+
+        let storage = map.entries()
+            .filter(|d| d.ty == MemoryType::CONVENTIONAL && d.phys_start != 0 && d.page_count >= pages)
+            .find(|d| stack.is_none_or(|stack| stack.phys_start != d.phys_start))?
+            .phys_start;
+
+        let bitmap = unsafe { core::slice::from_raw_parts_mut(storage as *mut u64, words) };
+        bitmap.fill(u64::MAX);
+
+        let mut allocator = Self { bitmap, frames, usable: 0, free: 0, next: 0 };
+
+        for descriptor in map.entries().filter(|d| is_usable(d)) {
+            allocator.release(descriptor.phys_start, end_of(descriptor));
+        }
+        allocator.usable = allocator.free;
+
+        allocator.reserve(0, PAGE_SIZE);
+        allocator.reserve(storage, storage + pages * PAGE_SIZE);
+        if let Some(stack) = stack {
+            allocator.reserve(stack.phys_start, end_of(stack));
+        }
+
+        unsafe {
+            let (gdt, gdt_end) = descriptor_table(|table| asm!("sgdt [{}]", in(reg) table, options(nostack)));
+            let (idt, idt_end) = descriptor_table(|table| asm!("sidt [{}]", in(reg) table, options(nostack)));
+            allocator.reserve(gdt, gdt_end);
+            allocator.reserve(idt, idt_end);
+
+            let (cr3, cr4): (u64, u64);
+            asm!("mov {}, cr3", out(reg) cr3, options(nomem, nostack));
+            asm!("mov {}, cr4", out(reg) cr4, options(nomem, nostack));
+            allocator.reserve_page_tables(cr3 & ADDRESS_MASK, if cr4 & LA57 != 0 { 5 } else { 4 });
+        }
+
+        Some(allocator)
+    }
+
+    fn is_used(&self, frame: usize) -> bool {
+        self.bitmap[frame / 64] & (1 << (frame % 64)) != 0
+    }
+
+    fn set(&mut self, frame: usize, used: bool) {
+        if frame >= self.frames || self.is_used(frame) == used {
+            return;
+        }
+
+        if used {
+            self.bitmap[frame / 64] |= 1 << (frame % 64);
+            self.free -= 1;
+        } else {
+            self.bitmap[frame / 64] &= !(1 << (frame % 64));
+            self.free += 1;
+        }
+    }
+
+    fn frames_between(start: u64, end: u64) -> core::ops::Range<usize> {
+        (start / PAGE_SIZE) as usize..end.div_ceil(PAGE_SIZE) as usize
+    }
+
+    fn release(&mut self, start: u64, end: u64) {
+        for frame in Self::frames_between(start, end) {
+            self.set(frame, false);
+        }
+    }
+
+    pub fn reserve(&mut self, start: u64, end: u64) {
+        for frame in Self::frames_between(start, end) {
+            self.set(frame, true);
+        }
+    }
+
+    unsafe fn reserve_page_tables(&mut self, table: u64, level: u8) {
+        self.reserve(table, table + PAGE_SIZE);
+        if level == 1 {
+            return;
+        }
+
+        for entry in core::slice::from_raw_parts(table as *const u64, 512) {
+            if entry & PRESENT == 0 || (level <= 3 && entry & HUGE_PAGE != 0) {
+                continue;
+            }
+            self.reserve_page_tables(entry & ADDRESS_MASK, level - 1);
+        }
+    }
+
+    pub fn alloc_frame(&mut self) -> Option<u64> {
+        self.alloc_frames(1)
+    }
+
+    pub fn alloc_frames(&mut self, count: usize) -> Option<u64> {
+        let mut run = 0;
+
+        for frame in (self.next..self.frames).chain(0..self.next) {
+            if frame == 0 || self.is_used(frame) {
+                run = 0;
+                continue;
+            }
+
+            run += 1;
+            if run == count {
+                let start = frame + 1 - count;
+                for frame in start..=frame {
+                    self.set(frame, true);
+                }
+                self.next = (frame + 1) % self.frames;
+                return Some(start as u64 * PAGE_SIZE);
+            }
+        }
+
+        None
+    }
+
+    pub fn free_frames(&mut self, address: u64, count: usize) {
+        self.release(address, address + count as u64 * PAGE_SIZE);
+    }
+
+    pub fn usable_bytes(&self) -> u64 {
+        self.usable as u64 * PAGE_SIZE
+    }
+
+    pub fn free_bytes(&self) -> u64 {
+        self.free as u64 * PAGE_SIZE
+    }
+}
+
+impl Heap {
+    const MIN_BLOCK: usize = size_of::<FreeBlock>();
+
+    const fn empty() -> Self {
+        Self { start: 0, size: 0, used: 0, head: ptr::null_mut() }
+    }
+
+    unsafe fn init(&mut self, start: usize, size: usize) {
+        let size = size - size % Self::MIN_BLOCK;
+        let block = start as *mut FreeBlock;
+        block.write(FreeBlock { size, next: ptr::null_mut() });
+        *self = Self { start, size, used: 0, head: block };
+    }
+
+    fn contains(&self, address: usize) -> bool {
+        address >= self.start && address - self.start < self.size
+    }
+
+    fn block_size(layout: Layout) -> usize {
+        layout.size().max(Self::MIN_BLOCK).next_multiple_of(Self::MIN_BLOCK)
+    }
+
+    unsafe fn allocate(&mut self, layout: Layout) -> *mut u8 {
+        let size = Self::block_size(layout);
+        let align = layout.align().max(Self::MIN_BLOCK);
+        let mut previous: *mut *mut FreeBlock = &mut self.head;
+
+        while !(*previous).is_null() {
+            let block = *previous;
+            let start = block as usize;
+            let end = start + (*block).size;
+
+            let mut address = start.next_multiple_of(align);
+            if address != start && address - start < Self::MIN_BLOCK {
+                address = (start + Self::MIN_BLOCK).next_multiple_of(align);
+            }
+
+            if address + size > end {
+                previous = &mut (*block).next;
+                continue;
+            }
+
+            let mut next = (*block).next;
+            if end > address + size {
+                let rest = (address + size) as *mut FreeBlock;
+                rest.write(FreeBlock { size: end - address - size, next });
+                next = rest;
+            }
+
+            if address > start {
+                (*block).size = address - start;
+                (*block).next = next;
+            } else {
+                *previous = next;
+            }
+
+            self.used += size;
+            return address as *mut u8;
+        }
+
+        ptr::null_mut()
+    }
+
+    unsafe fn deallocate(&mut self, pointer: *mut u8, layout: Layout) {
+        let size = Self::block_size(layout);
+        let address = pointer as usize;
+        let mut previous: *mut FreeBlock = ptr::null_mut();
+        let mut next = self.head;
+
+        while !next.is_null() && (next as usize) < address {
+            previous = next;
+            next = (*next).next;
+        }
+
+        let block = pointer as *mut FreeBlock;
+        block.write(FreeBlock { size, next });
+        self.used -= size;
+
+        if !next.is_null() && address + size == next as usize {
+            (*block).size += (*next).size;
+            (*block).next = (*next).next;
+        }
+
+        if previous.is_null() {
+            self.head = block;
+        } else if previous as usize + (*previous).size == address {
+            (*previous).size += (*block).size;
+            (*previous).next = (*block).next;
+        } else {
+            (*previous).next = block;
+        }
+    }
+}
+
+unsafe impl GlobalAlloc for SystemAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        if HEAP_READY.load(Ordering::Acquire) {
+            KERNEL_HEAP.lock(|heap| heap.allocate(layout))
+        } else if BOOT_SERVICES_EXITED.load(Ordering::Acquire) {
+            ptr::null_mut()
+        } else {
+            Allocator.alloc(layout)
+        }
+    }
+
+    unsafe fn dealloc(&self, pointer: *mut u8, layout: Layout) {
+        let released = HEAP_READY.load(Ordering::Acquire) && KERNEL_HEAP.lock(|heap| {
+            heap.contains(pointer as usize) && {
+                heap.deallocate(pointer, layout);
+                true
+            }
+        });
+
+        if !released && !BOOT_SERVICES_EXITED.load(Ordering::Acquire) {
+            Allocator.dealloc(pointer, layout);
+        }
+    }
+}
+
+#[allow(clippy::missing_safety_doc)]
+pub unsafe fn init_heap(start: u64, size: u64) {
+    KERNEL_HEAP.lock(|heap| heap.init(start as usize, size as usize));
+    HEAP_READY.store(true, Ordering::Release);
+}
+
+pub fn init_kernel_memory(map: &MemoryMap) -> Option<FrameAllocator> {
+    let mut frames = FrameAllocator::new(map)?;
+    let mut pages = HEAP_PAGES;
+
+    let heap = loop {
+        match frames.alloc_frames(pages) {
+            Some(heap) => break heap,
+            None if pages > MIN_HEAP_PAGES => pages /= 2,
+            None => return None
+        }
+    };
+
+    unsafe { init_heap(heap, pages as u64 * PAGE_SIZE) };
+    Some(frames)
+}
+
+pub fn memory_stats(frames: &FrameAllocator) -> MemoryStats {
+    let (heap_size, heap_used) = KERNEL_HEAP.lock(|heap| (heap.size as u64, heap.used as u64));
+    MemoryStats { total: frames.usable_bytes(), free: frames.free_bytes(), heap_size, heap_used }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::vec::Vec;
+
+    const ARENA_SIZE: usize = 4096;
+
+    #[repr(align(4096))]
+    struct Arena([u8; ARENA_SIZE]);
+
+    fn heap(arena: &mut Arena) -> Heap {
+        let mut heap = Heap::empty();
+        unsafe { heap.init(arena.0.as_mut_ptr() as usize, ARENA_SIZE) };
+        heap
+    }
+
+    fn free_blocks(heap: &Heap) -> Vec<(usize, usize)> {
+        let mut blocks = Vec::new();
+        let mut block = heap.head;
+        while !block.is_null() {
+            unsafe {
+                blocks.push((block as usize - heap.start, (*block).size));
+                block = (*block).next;
+            }
+        }
+        blocks
+    }
+
+    #[test]
+    fn allocates_aligned_blocks() {
+        let mut arena = Arena([0; ARENA_SIZE]);
+        let mut heap = heap(&mut arena);
+
+        let small = unsafe { heap.allocate(Layout::from_size_align(3, 1).unwrap()) };
+        let aligned = unsafe { heap.allocate(Layout::from_size_align(64, 256).unwrap()) };
+        assert_eq!(small as usize, heap.start);
+        assert_eq!(aligned as usize % 256, 0);
+        assert!(heap.contains(aligned as usize) && heap.contains(aligned as usize + 63));
+        assert_eq!(heap.used, Heap::MIN_BLOCK + 64);
+        assert_eq!(free_blocks(&heap), [(Heap::MIN_BLOCK, 256 - Heap::MIN_BLOCK), (320, ARENA_SIZE - 320)]);
+    }
+
+    #[test]
+    fn fails_when_exhausted() {
+        let mut arena = Arena([0; ARENA_SIZE]);
+        let mut heap = heap(&mut arena);
+
+        let all = unsafe { heap.allocate(Layout::from_size_align(ARENA_SIZE, 8).unwrap()) };
+        assert!(!all.is_null());
+        assert!(heap.head.is_null());
+        assert!(unsafe { heap.allocate(Layout::from_size_align(1, 1).unwrap()) }.is_null());
+        assert!(!heap.contains(heap.start + ARENA_SIZE));
+    }
+
+    #[test]
+    fn coalesces_freed_neighbours() {
+        let mut arena = Arena([0; ARENA_SIZE]);
+        let mut heap = heap(&mut arena);
+        let layout = Layout::from_size_align(128, 8).unwrap();
+
+        let blocks: Vec<*mut u8> = (0..4).map(|_| unsafe { heap.allocate(layout) }).collect();
+        assert_eq!(heap.used, 512);
+
+        unsafe {
+            heap.deallocate(blocks[0], layout);
+            heap.deallocate(blocks[2], layout);
+        }
+        assert_eq!(free_blocks(&heap), [(0, 128), (256, 128), (512, ARENA_SIZE - 512)]);
+
+        unsafe { heap.deallocate(blocks[1], layout) };
+        assert_eq!(free_blocks(&heap), [(0, 384), (512, ARENA_SIZE - 512)]);
+
+        unsafe { heap.deallocate(blocks[3], layout) };
+        assert_eq!(free_blocks(&heap), [(0, ARENA_SIZE)]);
+        assert_eq!(heap.used, 0);
+    }
+}
@@ -0,0 +1,189 @@
+use alloc::format;
+use alloc::string::String;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::fmt::{Display, Formatter};
+use uefi::prelude::*;
+use uefi::{CStr8, Error};
+use uefi::proto::network::IpAddress;
+use uefi::proto::network::pxe::{BaseCode, DhcpV4Packet};
+use uefi::table::boot::{OpenProtocolAttributes, OpenProtocolParams, ScopedProtocol};
+use crate::CoreServices;
+
+pub const NETWORK_VARIABLE: &str = "Russet.NetworkBoot";
+pub const NETWORK_PREFIX: &str = "tftp:";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NetworkPath {
+    pub server: Option<[u8; 4]>,
+    pub path: String
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BinarySource {
+    Disk,
+    Network
+}
+
+#[derive(Debug)]
+pub enum NetworkError {
+    NoInterface,
+    Start(Error),
+    Dhcp(Error),
+    NoServer,
+    TooLarge(u64),
+    Transfer(Error)
+}
+
+impl Display for NetworkError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        match self {
+            NetworkError::NoInterface => write!(f, "no network interface supports PXE"),
+            NetworkError::Start(e) => write!(f, "the network interface could not be started ({:?})", e.status()),
+            NetworkError::Dhcp(e) => write!(f, "no address was received over DHCP ({:?})", e.status()),
+            NetworkError::NoServer => write!(f, "DHCP did not name a boot server"),
+            NetworkError::TooLarge(size) => write!(f, "the file is too large ({size} bytes)"),
+            NetworkError::Transfer(e) => write!(f, "the transfer failed ({:?})", e.status())
+        }
+    }
+}
+
+fn parse_address(address: &str) -> Option<[u8; 4]> {
+    let mut octets = [0; 4];
+    let mut parts = address.split('.');
+
+    for octet in octets.iter_mut() {
+        *octet = parts.next()?.parse().ok()?;
+    }
+
+    parts.next().is_none().then_some(octets)
+}
+
+pub fn format_address(address: [u8; 4]) -> String {
+    format!("{}.{}.{}.{}", address[0], address[1], address[2], address[3])
+}
+
+impl NetworkPath {
+    pub fn parse(path: &str) -> Option<Self> {
+        let path = path.strip_prefix(NETWORK_PREFIX)?;
+
+        let (server, path) = match path.strip_prefix("//") {
+            Some(rest) => {
+                let (server, path) = rest.split_once('/')?;
+                (Some(parse_address(server)?), path)
+            },
+            None => (None, path)
+        };
+
+        match path.trim_start_matches('/') {
+            "" => None,
+            path => Some(Self { server, path: format!("/{path}") })
+        }
+    }
+}
+
+pub fn is_network_path(path: &str) -> bool {
+    path.starts_with(NETWORK_PREFIX)
+}
+
+impl CoreServices {
+    fn base_code(&self) -> Option<ScopedProtocol<'_, BaseCode>> {
+        let boot_services = self.system_table.boot_services();
+        let handle = *boot_services.find_handles::<BaseCode>().ok()?.first()?;
+
+        unsafe {
+            boot_services.open_protocol::<BaseCode>(OpenProtocolParams {
+                handle,
+                agent: boot_services.image_handle(),
+                controller: None
+            }, OpenProtocolAttributes::GetProtocol)
+        }.ok()
+    }
+
+    pub fn start_network(&self, server: Option<[u8; 4]>) -> Result<[u8; 4], NetworkError> {
+        let mut base_code = self.base_code().ok_or(NetworkError::NoInterface)?;
+
+        if !base_code.mode().started {
+            base_code.start(false).map_err(NetworkError::Start)?;
+        }
+
+        if !base_code.mode().dhcp_ack_received {
+            base_code.dhcp(true).map_err(NetworkError::Dhcp)?;
+        }
+
+        let ack: &DhcpV4Packet = base_code.mode().dhcp_ack.as_ref();
+        let server = server.unwrap_or(ack.bootp_si_addr);
+
+        if server == [0; 4] {
+            return Err(NetworkError::NoServer);
+        }
+
+        let _ = self.set_shared_variable(NETWORK_VARIABLE, &server);
+        Ok(server)
+    }
+
+    pub fn network_server(&self) -> Option<[u8; 4]> {
+        let (data, _) = self.get_shared_variable(NETWORK_VARIABLE).ok()?;
+        data.try_into().ok()
+    }
+
+    pub fn tftp_read(&self, server: [u8; 4], path: &str) -> Result<Vec<u8>, NetworkError> {
+        let mut base_code = self.base_code().ok_or(NetworkError::NoInterface)?;
+        let server = IpAddress::new_v4(server);
+
+        let mut name = path.trim_start_matches(['/', '\\']).replace('\\', "/").into_bytes();
+        name.push(0);
+        let name = CStr8::from_bytes_with_nul(&name).map_err(|_| NetworkError::Transfer(Status::INVALID_PARAMETER.into()))?;
+
+        let size = base_code.tftp_get_file_size(&server, name).map_err(NetworkError::Transfer)?;
+        if size > relf::MAX_SIZE as u64 {
+            return Err(NetworkError::TooLarge(size));
+        }
+
+        let mut data = vec![0; size as usize];
+        if size > 0 {
+            let read = base_code.tftp_read_file(&server, name, Some(&mut data)).map_err(NetworkError::Transfer)?;
+            data.truncate(read as usize);
+        }
+
+        Ok(data)
+    }
+
+    pub(crate) fn network_binary(&self, path: &str) -> Option<Vec<u8>> {
+        let server = self.network_server()?;
+        match self.tftp_read(server, path) {
+            Ok(data) => {
+                log::info!("{path} fetched from {}", format_address(server));
+                Some(data)
+            },
+            Err(e) => {
+                log::warn!("{path} could not be fetched from {}, using the disk copy: {e}", format_address(server));
+                None
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_paths_with_and_without_server() {
+        assert_eq!(NetworkPath::parse("tftp:/System/Kernel"), Some(NetworkPath { server: None, path: "/System/Kernel".into() }));
+        assert_eq!(NetworkPath::parse("tftp:System/Kernel"), Some(NetworkPath { server: None, path: "/System/Kernel".into() }));
+        assert_eq!(NetworkPath::parse("tftp://10.0.2.2/System/Kernel"),
+            Some(NetworkPath { server: Some([10, 0, 2, 2]), path: "/System/Kernel".into() }));
+    }
+
+    #[test]
+    fn rejects_invalid_paths() {
+        assert_eq!(NetworkPath::parse("/System/Kernel"), None);
+        assert_eq!(NetworkPath::parse("tftp:"), None);
+        assert_eq!(NetworkPath::parse("tftp://10.0.2.2/"), None);
+        assert_eq!(NetworkPath::parse("tftp://10.0.2.2"), None);
+        assert_eq!(NetworkPath::parse("tftp://10.0.2/System/Kernel"), None);
+        assert_eq!(NetworkPath::parse("tftp://10.0.2.256/System/Kernel"), None);
+        assert_eq!(NetworkPath::parse("tftp://10.0.2.2.1/System/Kernel"), None);
+    }
+}
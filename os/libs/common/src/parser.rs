@@ -137,6 +137,17 @@ impl Command {
         })
     }
 
+    pub fn has(&self, name: &str) -> bool {
+        self.args.contains_key(name)
+    }
+
+    pub fn value(&self, name: &str) -> Option<&str> {
+        match self.args.get(name) {
+            Some(CommandArgument::Value(value)) => Some(value),
+            _ => None
+        }
+    }
+
     pub fn to_bytes(&self) -> Vec<u8> {
         self.into()
     }
@@ -0,0 +1,218 @@
+use core::arch::asm;
+use core::fmt::{self, Arguments, Write};
+use core::sync::atomic::{AtomicU8, Ordering};
+use uefi::Char16;
+use uefi::proto::console::text::{Key, Output, ScanCode};
+use uefi::proto::device_path::{DevicePath, DeviceSubType, DeviceType};
+use uefi::table::boot::{OpenProtocolAttributes, OpenProtocolParams};
+use crate::CoreServices;
+
+pub const COM1: u16 = 0x3f8;
+pub const SERIAL_VARIABLE: &str = "Russet.Serial";
+
+const DATA: u16 = 0;
+const INTERRUPT_ENABLE: u16 = 1;
+const FIFO_CONTROL: u16 = 2;
+const LINE_CONTROL: u16 = 3;
+const MODEM_CONTROL: u16 = 4;
+const LINE_STATUS: u16 = 5;
+const SCRATCH: u16 = 7;
+const DATA_READY: u8 = 0x01;
+const TRANSMIT_EMPTY: u8 = 0x20;
+const ESCAPE_POLLS: usize = 100_000;
+
+static MODE: AtomicU8 = AtomicU8::new(SerialMode::Off as u8);
+
+#[repr(u8)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SerialMode {
+    Off = 0,
+    Output = 1,
+    Full = 2
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct SerialPort {
+    base: u16
+}
+
+unsafe fn outb(port: u16, value: u8) {
+    asm!("out dx, al", in("dx") port, in("al") value, options(nomem, nostack, preserves_flags));
+}
+
+unsafe fn inb(port: u16) -> u8 {
+    let value: u8;
+    asm!("in al, dx", out("al") value, in("dx") port, options(nomem, nostack, preserves_flags));
+    value
+}
+
+impl SerialPort {
+    pub const fn new(base: u16) -> Self {
+        Self { base }
+    }
+
+    #[allow(clippy::missing_safety_doc)]
+    pub unsafe fn init(&self) {
+        outb(self.base + INTERRUPT_ENABLE, 0x00);
+        outb(self.base + LINE_CONTROL, 0x80);
+        outb(self.base + DATA, 0x01);
+        outb(self.base + INTERRUPT_ENABLE, 0x00);
+        outb(self.base + LINE_CONTROL, 0x03);
+        outb(self.base + FIFO_CONTROL, 0xc7);
+        outb(self.base + MODEM_CONTROL, 0x03);
+    }
+
+    pub fn present(&self) -> bool {
+        unsafe {
+            outb(self.base + SCRATCH, 0x5a);
+            let first = inb(self.base + SCRATCH);
+            outb(self.base + SCRATCH, 0xa5);
+            first == 0x5a && inb(self.base + SCRATCH) == 0xa5
+        }
+    }
+
+    pub fn read_byte(&self) -> Option<u8> {
+        unsafe { (inb(self.base + LINE_STATUS) & DATA_READY != 0).then(|| inb(self.base + DATA)) }
+    }
+
+    fn wait_byte(&self) -> Option<u8> {
+        (0..ESCAPE_POLLS).find_map(|_| self.read_byte())
+    }
+
+    pub fn read_key(&self) -> Option<Key> {
+        let key = match self.read_byte()? {
+            0x1b => match (self.wait_byte(), self.wait_byte()) {
+                (Some(b'['), Some(b'A')) => return Some(Key::Special(ScanCode::UP)),
+                (Some(b'['), Some(b'B')) => return Some(Key::Special(ScanCode::DOWN)),
+                (Some(b'['), Some(b'C')) => return Some(Key::Special(ScanCode::RIGHT)),
+                (Some(b'['), Some(b'D')) => return Some(Key::Special(ScanCode::LEFT)),
+                _ => return Some(Key::Special(ScanCode::ESCAPE))
+            },
+            b'\n' => '\r',
+            0x7f => '\x08',
+            byte if byte.is_ascii() => byte as char,
+            _ => return None
+        };
+
+        Char16::try_from(key).ok().map(Key::Printable)
+    }
+
+    pub fn write_byte(&self, byte: u8) {
+        unsafe {
+            while inb(self.base + LINE_STATUS) & TRANSMIT_EMPTY == 0 {
+                core::hint::spin_loop();
+            }
+            outb(self.base + DATA, byte);
+        }
+    }
+}
+
+impl Write for SerialPort {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        for byte in s.bytes() {
+            match byte {
+                b'\n' => {
+                    self.write_byte(b'\r');
+                    self.write_byte(b'\n');
+                },
+                0x08 => {
+                    self.write_byte(0x08);
+                    self.write_byte(b' ');
+                    self.write_byte(0x08);
+                },
+                byte => self.write_byte(byte)
+            }
+        }
+        Ok(())
+    }
+}
+
+impl SerialMode {
+    pub fn parse(value: &str) -> Option<Option<Self>> {
+        match value {
+            "auto" => Some(None),
+            "yes" | "true" | "on" => Some(Some(SerialMode::Full)),
+            "output" => Some(Some(SerialMode::Output)),
+            "no" | "false" | "off" => Some(Some(SerialMode::Off)),
+            _ => None
+        }
+    }
+}
+
+impl From<u8> for SerialMode {
+    fn from(value: u8) -> Self {
+        match value {
+            1 => SerialMode::Output,
+            2 => SerialMode::Full,
+            _ => SerialMode::Off
+        }
+    }
+}
+
+pub fn mode() -> SerialMode {
+    SerialMode::from(MODE.load(Ordering::Acquire))
+}
+
+pub fn set_mode(mode: SerialMode) {
+    MODE.store(mode as u8, Ordering::Release);
+}
+
+pub fn mirror(args: Arguments) {
+    if mode() != SerialMode::Off {
+        let _ = SerialPort::new(COM1).write_fmt(args);
+    }
+}
+
+pub fn read_key() -> Option<Key> {
+    match mode() {
+        SerialMode::Full => SerialPort::new(COM1).read_key(),
+        _ => None
+    }
+}
+
+pub fn clear() {
+    mirror(format_args!("\x1b[2J\x1b[H"));
+}
+
+pub fn highlight(enabled: bool) {
+    mirror(format_args!("{}", if enabled { "\x1b[7m" } else { "\x1b[0m" }));
+}
+
+impl CoreServices {
+    pub fn firmware_serial_console(&self) -> bool {
+        let boot_services = self.system_table.boot_services();
+        let handles = boot_services.find_handles::<Output>().unwrap_or_default();
+
+        handles.into_iter().any(|handle| {
+            let path = unsafe {
+                boot_services.open_protocol::<DevicePath>(OpenProtocolParams {
+                    handle,
+                    agent: boot_services.image_handle(),
+                    controller: None
+                }, OpenProtocolAttributes::GetProtocol)
+            };
+
+            path.is_ok_and(|path| path.node_iter().any(|node| {
+                node.device_type() == DeviceType::MESSAGING && node.sub_type() == DeviceSubType::MESSAGING_UART
+            }))
+        })
+    }
+
+    pub fn begin_serial(&self, setting: Option<SerialMode>) -> SerialMode {
+        let port = SerialPort::new(COM1);
+        let mode = match setting {
+            _ if !port.present() => SerialMode::Off,
+            None if self.firmware_serial_console() => SerialMode::Off,
+            None => SerialMode::Full,
+            Some(mode) => mode
+        };
+
+        if mode != SerialMode::Off {
+            unsafe { port.init() };
+        }
+
+        set_mode(mode);
+        let _ = self.set_shared_variable(SERIAL_VARIABLE, &[mode as u8]);
+        mode
+    }
+}
@@ -0,0 +1,172 @@
+use alloc::vec;
+use alloc::vec::Vec;
+use uefi::proto::console::gop::{BltOp, BltPixel, BltRegion, GraphicsOutput};
+use uefi::table::boot::{OpenProtocolAttributes, OpenProtocolParams, ScopedProtocol};
+use crate::CoreServices;
+
+pub const SPLASH_VARIABLE: &str = "Russet.Splash";
+pub const SPLASH_IMAGE: &str = "\\rootfs\\System\\Splash.bmp";
+
+const BACKGROUND: BltPixel = BltPixel::new(0x1a, 0x12, 0x0e);
+const RUSSET: BltPixel = BltPixel::new(0x80, 0x46, 0x1b);
+const HIGHLIGHT: BltPixel = BltPixel::new(0xc0, 0x7a, 0x3c);
+const TRACK: BltPixel = BltPixel::new(0x3a, 0x2a, 0x20);
+const LOGO_SIZE: usize = 128;
+const BAR_WIDTH: usize = 240;
+const BAR_HEIGHT: usize = 6;
+const BAR_GAP: usize = 48;
+
+#[repr(u8)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Stage {
+    Bootloader = 1,
+    Kernel = 2,
+    Init = 3,
+    Shell = 4
+}
+
+const STAGES: usize = 4;
+
+struct Image {
+    width: usize,
+    height: usize,
+    pixels: Vec<BltPixel>
+}
+
+impl Image {
+    fn logo() -> Self {
+        let center = LOGO_SIZE as i64 / 2;
+        let radius = center - 4;
+        let mut pixels = vec![BACKGROUND; LOGO_SIZE * LOGO_SIZE];
+
+        for y in 0..LOGO_SIZE as i64 {
+            for x in 0..LOGO_SIZE as i64 {
+                let (dx, dy) = (x - center, (y - center) * 5 / 4);
+                let (hx, hy) = (x - center + radius / 3, y - center + radius / 2);
+
+                if dx * dx + dy * dy > radius * radius {
+                    continue;
+                }
+
+                pixels[y as usize * LOGO_SIZE + x as usize] = if hx * hx + hy * hy < radius * radius / 16 {
+                    HIGHLIGHT
+                } else {
+                    RUSSET
+                };
+            }
+        }
+
+        Self { width: LOGO_SIZE, height: LOGO_SIZE, pixels }
+    }
+
+    fn bmp(data: &[u8]) -> Option<Self> {
+        let u16_at = |offset: usize| Some(u16::from_le_bytes(data.get(offset..offset + 2)?.try_into().ok()?));
+        let u32_at = |offset: usize| Some(u32::from_le_bytes(data.get(offset..offset + 4)?.try_into().ok()?));
+
+        if data.get(..2) != Some(b"BM") || u32_at(30)? != 0 {
+            return None;
+        }
+
+        let offset = u32_at(10)? as usize;
+        let width = u32_at(18)? as i32;
+        let height = u32_at(22)? as i32;
+        let bytes_per_pixel = match u16_at(28)? {
+            24 => 3,
+            32 => 4,
+            _ => return None
+        };
+
+        if width <= 0 || height == 0 || width > 4096 || height.unsigned_abs() > 4096 {
+            return None;
+        }
+
+        let (width, rows) = (width as usize, height.unsigned_abs() as usize);
+        let stride = (width * bytes_per_pixel).next_multiple_of(4);
+        let mut pixels = Vec::with_capacity(width * rows);
+
+        for row in 0..rows {
+            let source = if height > 0 { rows - 1 - row } else { row };
+            let line = data.get(offset + source * stride..offset + source * stride + width * bytes_per_pixel)?;
+            pixels.extend(line.chunks_exact(bytes_per_pixel).map(|pixel| BltPixel::new(pixel[2], pixel[1], pixel[0])));
+        }
+
+        Some(Self { width, height: rows, pixels })
+    }
+}
+
+impl CoreServices {
+    pub(crate) fn graphics_output(&self) -> Option<ScopedProtocol<'_, GraphicsOutput>> {
+        let boot_services = self.system_table.boot_services();
+        let handle = boot_services.get_handle_for_protocol::<GraphicsOutput>().ok()?;
+
+        unsafe {
+            boot_services.open_protocol::<GraphicsOutput>(OpenProtocolParams {
+                handle,
+                agent: boot_services.image_handle(),
+                controller: None
+            }, OpenProtocolAttributes::GetProtocol)
+        }.ok()
+    }
+
+    pub fn splash_active(&self) -> bool {
+        matches!(self.get_shared_variable(SPLASH_VARIABLE), Ok((data, _)) if data == [1])
+    }
+
+    pub fn begin_splash(&mut self) -> bool {
+        if self.graphics_output().is_none() || self.set_shared_variable(SPLASH_VARIABLE, &[1]).is_err() {
+            return false;
+        }
+
+        let _ = self.system_table.stdout().enable_cursor(false);
+        self.splash_progress(Stage::Bootloader)
+    }
+
+    pub fn splash_progress(&mut self, stage: Stage) -> bool {
+        if !self.splash_active() {
+            return false;
+        }
+
+        if let Ok(Some(_)) = self.system_table.stdin().read_key() {
+            self.end_splash();
+            return false;
+        }
+
+        let Some(mut gop) = self.graphics_output() else {
+            self.end_splash();
+            return false;
+        };
+
+        let (width, height) = gop.current_mode_info().resolution();
+        let image = self.fs.read_bytes(SPLASH_IMAGE)
+            .and_then(|data| Image::bmp(&data))
+            .filter(|image| image.width <= width && image.height + BAR_GAP + BAR_HEIGHT <= height)
+            .unwrap_or_else(Image::logo);
+
+        let top = height.saturating_sub(image.height + BAR_GAP + BAR_HEIGHT) / 2;
+        let bar = (width.saturating_sub(BAR_WIDTH) / 2, top + image.height + BAR_GAP);
+        let filled = BAR_WIDTH * stage as usize / STAGES;
+
+        let _ = gop.blt(BltOp::VideoFill { color: BACKGROUND, dest: (0, 0), dims: (width, height) });
+        let _ = gop.blt(BltOp::BufferToVideo {
+            buffer: &image.pixels,
+            src: BltRegion::Full,
+            dest: (width.saturating_sub(image.width) / 2, top),
+            dims: (image.width, image.height)
+        });
+        let _ = gop.blt(BltOp::VideoFill { color: TRACK, dest: bar, dims: (BAR_WIDTH, BAR_HEIGHT) });
+        let _ = gop.blt(BltOp::VideoFill { color: HIGHLIGHT, dest: bar, dims: (filled, BAR_HEIGHT) });
+
+        true
+    }
+
+    pub fn end_splash(&mut self) {
+        if !self.splash_active() {
+            return;
+        }
+
+        let _ = self.set_shared_variable(SPLASH_VARIABLE, &[0]);
+        let stdout = self.system_table.stdout();
+        let _ = stdout.reset(false);
+        let _ = stdout.enable_cursor(true);
+    }
+}
@@ -0,0 +1,180 @@
+use alloc::string::String;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::arch::x86_64::__cpuid;
+use uefi::table::boot::MemoryType;
+use crate::CoreServices;
+use crate::memory::PAGE_SIZE;
+use crate::serial::{self, SerialMode};
+
+const MAP_SLACK: usize = 8;
+
+const BASIC_FEATURES: [(u32, &str); 7] = [
+    (0, "fpu"), (4, "tsc"), (6, "pae"), (9, "apic"), (25, "sse"), (26, "sse2"), (28, "htt")
+];
+
+const EXTENDED_FEATURES: [(u32, &str); 13] = [
+    (0, "sse3"), (9, "ssse3"), (12, "fma"), (13, "cx16"), (19, "sse4.1"), (20, "sse4.2"), (21, "x2apic"),
+    (23, "popcnt"), (25, "aes"), (26, "xsave"), (28, "avx"), (30, "rdrand"), (31, "hypervisor")
+];
+
+const STRUCTURED_FEATURES: [(u32, &str); 7] = [
+    (3, "bmi1"), (5, "avx2"), (8, "bmi2"), (9, "erms"), (16, "avx512f"), (18, "rdseed"), (29, "sha")
+];
+
+const LONG_MODE_FEATURES: [(u32, &str); 3] = [
+    (20, "nx"), (26, "pdpe1gb"), (29, "lm")
+];
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CpuInformation {
+    pub vendor: String,
+    pub brand: String,
+    pub hypervisor: Option<String>,
+    pub family: u32,
+    pub model: u32,
+    pub stepping: u32,
+    pub features: Vec<&'static str>
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MemoryInformation {
+    pub total: u64,
+    pub free: u64,
+    pub boot: u64,
+    pub firmware: u64,
+    pub reserved: u64
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ConsoleInformation {
+    pub columns: usize,
+    pub rows: usize,
+    pub graphics: Option<(usize, usize)>,
+    pub serial: SerialMode
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SystemInformation {
+    pub os_string: String,
+    pub bootloader: String,
+    pub kernel: String,
+    pub init: String,
+    pub firmware_vendor: String,
+    pub firmware_revision: u32,
+    pub uefi_revision: String,
+    pub cpu: CpuInformation,
+    pub memory: Option<MemoryInformation>,
+    pub console: ConsoleInformation
+}
+
+fn registers_text(registers: &[u32]) -> String {
+    let bytes: Vec<u8> = registers.iter().flat_map(|register| register.to_le_bytes()).collect();
+    String::from_utf8_lossy(&bytes).trim_matches(|c: char| c == '\0' || c.is_whitespace()).into()
+}
+
+fn features(register: u32, names: &[(u32, &'static str)], into: &mut Vec<&'static str>) {
+    into.extend(names.iter().filter(|(bit, _)| register & (1 << bit) != 0).map(|(_, name)| *name));
+}
+
+pub fn cpu_information() -> CpuInformation {
+    let leaf = __cpuid(0);
+    let vendor = registers_text(&[leaf.ebx, leaf.edx, leaf.ecx]);
+    let max_leaf = leaf.eax;
+
+    let info = __cpuid(1);
+    let base_family = (info.eax >> 8) & 0xf;
+    let base_model = (info.eax >> 4) & 0xf;
+    let family = match base_family {
+        0xf => base_family + ((info.eax >> 20) & 0xff),
+        _ => base_family
+    };
+    let model = match base_family {
+        0x6 | 0xf => base_model | (((info.eax >> 16) & 0xf) << 4),
+        _ => base_model
+    };
+
+    let mut list = Vec::new();
+    features(info.edx, &BASIC_FEATURES, &mut list);
+    features(info.ecx, &EXTENDED_FEATURES, &mut list);
+    if max_leaf >= 7 {
+        features(__cpuid(7).ebx, &STRUCTURED_FEATURES, &mut list);
+    }
+
+    let max_extended = __cpuid(0x8000_0000).eax;
+    if max_extended >= 0x8000_0001 {
+        features(__cpuid(0x8000_0001).edx, &LONG_MODE_FEATURES, &mut list);
+    }
+
+    let brand = match max_extended {
+        0x8000_0004.. => {
+            let registers: Vec<u32> = (0x8000_0002..=0x8000_0004).map(__cpuid)
+                .flat_map(|leaf| [leaf.eax, leaf.ebx, leaf.ecx, leaf.edx])
+                .collect();
+            registers_text(&registers)
+        },
+        _ => String::new()
+    };
+
+    let hypervisor = (info.ecx & (1 << 31) != 0).then(|| {
+        let leaf = __cpuid(0x4000_0000);
+        registers_text(&[leaf.ebx, leaf.ecx, leaf.edx])
+    });
+
+    CpuInformation { vendor, brand, hypervisor, family, model, stepping: info.eax & 0xf, features: list }
+}
+
+impl CoreServices {
+    pub fn memory_information(&self) -> Option<MemoryInformation> {
+        let boot_services = self.system_table.boot_services();
+        let size = boot_services.memory_map_size();
+        let mut buffer = vec![0; size.map_size + MAP_SLACK * size.entry_size];
+        let map = boot_services.memory_map(&mut buffer).ok()?;
+        let mut memory = MemoryInformation { total: 0, free: 0, boot: 0, firmware: 0, reserved: 0 };
+
+        for descriptor in map.entries() {
+            let size = descriptor.page_count * PAGE_SIZE;
+            match descriptor.ty {
+                MemoryType::MMIO | MemoryType::MMIO_PORT_SPACE => continue,
+                MemoryType::CONVENTIONAL => memory.free += size,
+                MemoryType::LOADER_CODE | MemoryType::LOADER_DATA
+                | MemoryType::BOOT_SERVICES_CODE | MemoryType::BOOT_SERVICES_DATA => memory.boot += size,
+                MemoryType::RUNTIME_SERVICES_CODE | MemoryType::RUNTIME_SERVICES_DATA
+                | MemoryType::ACPI_RECLAIM | MemoryType::ACPI_NON_VOLATILE | MemoryType::PAL_CODE => memory.firmware += size,
+                _ => memory.reserved += size
+            }
+            memory.total += size;
+        }
+
+        Some(memory)
+    }
+
+    pub fn console_information(&mut self) -> ConsoleInformation {
+        let (columns, rows) = match self.system_table.stdout().current_mode() {
+            Ok(Some(mode)) => (mode.columns(), mode.rows()),
+            _ => (0, 0)
+        };
+
+        let graphics = self.graphics_output().map(|gop| gop.current_mode_info().resolution());
+        ConsoleInformation { columns, rows, graphics, serial: serial::mode() }
+    }
+
+    pub fn system_information(&mut self) -> SystemInformation {
+        let string = |name| self.get_shared_variable(name)
+            .map(|(data, _)| String::from_utf8_lossy(&data).into_owned())
+            .unwrap_or_default();
+
+        SystemInformation {
+            os_string: string("Russet.OSString"),
+            bootloader: string("Russet.Bootloader"),
+            kernel: string("Russet.Version"),
+            init: string("Russet.Init"),
+            firmware_vendor: self.firmware_vendor(),
+            firmware_revision: self.firmware_revision(),
+            uefi_revision: self.uefi_revision(),
+            cpu: cpu_information(),
+            memory: self.memory_information(),
+            console: self.console_information()
+        }
+    }
+}
@@ -0,0 +1,159 @@
+use alloc::string::String;
+use alloc::vec::Vec;
+use crate::CoreServices;
+
+pub const TIMELINE_VARIABLE: &str = "Russet.BootTimeline";
+
+const CALIBRATION_US: usize = 10_000;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Mark {
+    pub label: String,
+    pub tsc: u64
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Timeline {
+    pub frequency: u64,
+    pub closed: bool,
+    pub marks: Vec<Mark>
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Phase<'a> {
+    pub label: &'a str,
+    pub duration: u64,
+    pub elapsed: u64
+}
+
+pub fn timestamp() -> u64 {
+    unsafe { core::arch::x86_64::_rdtsc() }
+}
+
+impl Timeline {
+    pub fn parse(data: &[u8]) -> Option<Self> {
+        let frequency = u64::from_le_bytes(data.get(..8)?.try_into().ok()?);
+        let closed = *data.get(8)? != 0;
+        let mut marks = Vec::new();
+        let mut rest = &data[9..];
+
+        while !rest.is_empty() {
+            let tsc = u64::from_le_bytes(rest.get(..8)?.try_into().ok()?);
+            let len = *rest.get(8)? as usize;
+            let label = String::from_utf8(rest.get(9..9 + len)?.to_vec()).ok()?;
+            marks.push(Mark { label, tsc });
+            rest = &rest[9 + len..];
+        }
+
+        Some(Self { frequency, closed, marks })
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        bytes.extend_from_slice(&self.frequency.to_le_bytes());
+        bytes.push(self.closed as u8);
+
+        for mark in &self.marks {
+            let mut end = mark.label.len().min(u8::MAX as usize);
+            while !mark.label.is_char_boundary(end) {
+                end -= 1;
+            }
+            let label = &mark.label.as_bytes()[..end];
+            bytes.extend_from_slice(&mark.tsc.to_le_bytes());
+            bytes.push(label.len() as u8);
+            bytes.extend_from_slice(label);
+        }
+
+        bytes
+    }
+
+    pub fn micros(&self, ticks: u64) -> u64 {
+        match self.frequency {
+            0 => 0,
+            frequency => (ticks as u128 * 1_000_000 / frequency as u128) as u64
+        }
+    }
+
+    pub fn phases(&self) -> impl Iterator<Item = Phase<'_>> {
+        self.marks.iter().scan(0, move |previous, mark| {
+            let duration = mark.tsc.saturating_sub(*previous);
+            *previous = mark.tsc;
+            Some(Phase {
+                label: &mark.label,
+                duration: self.micros(duration),
+                elapsed: self.micros(mark.tsc)
+            })
+        })
+    }
+}
+
+impl CoreServices {
+    pub fn boot_timeline(&self) -> Option<Timeline> {
+        let (data, _) = self.get_shared_variable(TIMELINE_VARIABLE).ok()?;
+        Timeline::parse(&data)
+    }
+
+    pub fn begin_timeline(&self, label: &str) {
+        let start = timestamp();
+        self.system_table.boot_services().stall(CALIBRATION_US);
+        let frequency = (timestamp() - start) * (1_000_000 / CALIBRATION_US as u64);
+
+        let timeline = Timeline { frequency, closed: false, marks: alloc::vec![Mark { label: String::from(label), tsc: start }] };
+        let _ = self.set_shared_variable(TIMELINE_VARIABLE, &timeline.to_bytes());
+    }
+
+    pub fn mark(&self, label: &str) {
+        self.record(label, false);
+    }
+
+    pub fn close_timeline(&self, label: &str) {
+        self.record(label, true);
+    }
+
+    fn record(&self, label: &str, close: bool) {
+        let tsc = timestamp();
+        let Some(mut timeline) = self.boot_timeline().filter(|timeline| !timeline.closed) else {
+            return;
+        };
+
+        timeline.marks.push(Mark { label: String::from(label), tsc });
+        timeline.closed = close;
+        let _ = self.set_shared_variable(TIMELINE_VARIABLE, &timeline.to_bytes());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::vec;
+
+    fn timeline(labels: &[&str]) -> Timeline {
+        let marks = labels.iter().enumerate().map(|(i, label)| Mark { label: String::from(*label), tsc: i as u64 * 1000 }).collect();
+        Timeline { frequency: 1_000_000, closed: true, marks }
+    }
+
+    #[test]
+    fn round_trips_through_bytes() {
+        let timeline = timeline(&["firmware", "kernel loaded", "init ready"]);
+        assert_eq!(Timeline::parse(&timeline.to_bytes()), Some(timeline));
+    }
+
+    #[test]
+    fn truncates_long_labels_on_char_boundary() {
+        let label: String = "é".repeat(200);
+        let parsed = Timeline::parse(&timeline(&[&label]).to_bytes()).unwrap();
+        assert_eq!(parsed.marks[0].label, "é".repeat(127));
+
+        let label: String = "a".repeat(300);
+        let parsed = Timeline::parse(&timeline(&[&label]).to_bytes()).unwrap();
+        assert_eq!(parsed.marks[0].label.len(), 255);
+    }
+
+    #[test]
+    fn phases_measure_time_between_marks() {
+        let timeline = timeline(&["a", "b", "c"]);
+        let phases: Vec<Phase> = timeline.phases().collect();
+        assert_eq!(phases.iter().map(|phase| (phase.duration, phase.elapsed)).collect::<Vec<_>>(), vec![(0, 0), (1000, 1000), (1000, 2000)]);
+        assert_eq!(Timeline::parse(&[0; 4]), None);
+    }
+}
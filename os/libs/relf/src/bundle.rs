@@ -0,0 +1,71 @@
+use alloc::vec::Vec;
+use elf::ElfBytes;
+use elf::endian::AnyEndian;
+use crate::{Error, Header, Metadata, Note, Signature, SymbolTable};
+
+#[derive(Debug, Clone)]
+pub struct Bundle<'a> {
+    pub header: Header,
+    pub payload: &'a [u8],
+    pub signature: Option<Signature>,
+    pub metadata: Option<Metadata>,
+    pub symbols: Option<&'a [u8]>
+}
+
+fn section<'a>(file: &ElfBytes<'a, AnyEndian>, name: &str) -> Result<Option<&'a [u8]>, Error> {
+    let Some(header) = file.section_header_by_name(name).map_err(|_| Error::InvalidElf)? else {
+        return Ok(None);
+    };
+
+    match file.section_data(&header).map_err(|_| Error::InvalidElf)? {
+        (data, None) => Ok(Some(data)),
+        (_, Some(_)) => Err(Error::InvalidElf)
+    }
+}
+
+impl<'a> Bundle<'a> {
+    pub fn parse(elf: &'a [u8]) -> Result<Self, Error> {
+        let file = ElfBytes::<AnyEndian>::minimal_parse(elf).map_err(|_| Error::InvalidElf)?;
+
+        let mut notes = section(&file, crate::NOTE_SECTION)?.ok_or(Error::SectionNotFound(crate::NOTE_SECTION))?;
+        let mut header = None;
+        let mut metadata = None;
+
+        while !notes.is_empty() {
+            let (note, rest) = Note::parse(notes)?;
+
+            if note.is_russet() {
+                match note.n_type {
+                    crate::NOTE_TYPE_HEADER if header.is_none() => header = Some(Header::decode(note.desc)?),
+                    crate::NOTE_TYPE_METADATA if metadata.is_none() => metadata = Some(Metadata::decode(note.desc)?),
+                    _ => ()
+                }
+            }
+
+            notes = rest;
+        }
+
+        let header = header.ok_or(Error::NotRusset)?;
+        let payload = section(&file, crate::PAYLOAD_SECTION)?.ok_or(Error::SectionNotFound(crate::PAYLOAD_SECTION))?;
+
+        let signature = match section(&file, crate::SIGNATURE_SECTION)? {
+            Some(data) => Some(Signature::from_note(&Note::parse(data)?.0)?),
+            None => None
+        };
+
+        let symbols = section(&file, crate::DEBUG_SECTION)?
+            .filter(|data| SymbolTable::parse(data).is_ok());
+
+        Ok(Self { header, payload, signature, metadata, symbols })
+    }
+
+    pub fn pe(&self) -> Result<Vec<u8>, Error> {
+        let pe = self.header.decompress(self.payload)?;
+
+        if self.header.verify(&pe) {
+            Ok(pe)
+        } else {
+            Err(Error::Corrupted)
+        }
+    }
+}
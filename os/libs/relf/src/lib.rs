@@ -0,0 +1,704 @@
+#![no_std]
+extern crate alloc;
+
+mod bundle;
+
+use alloc::string::String;
+use alloc::vec;
+use alloc::vec::Vec;
+use ed25519_compact::PublicKey;
+
+pub use crate::bundle::Bundle;
+
+pub const NOTE_SECTION: &str = ".note.tag";
+pub const PAYLOAD_SECTION: &str = ".text";
+pub const DEBUG_SECTION: &str = ".debug";
+pub const SIGNATURE_SECTION: &str = ".signature";
+
+pub const NOTE_NAME: &str = "Russet ";
+pub const NOTE_TYPE_HEADER: u32 = 1;
+pub const NOTE_TYPE_SIGNATURE: u32 = 2;
+pub const NOTE_TYPE_METADATA: u32 = 3;
+
+pub const HEADER_VERSION: u32 = 1;
+pub const ABI_VERSION: u32 = 2;
+pub const SUPPORTED_ABI: [u32; 1] = [2];
+
+pub const MAX_SIZE: usize = 64 * 1024 * 1024;
+
+const MAX_LZ4_RATIO: usize = 255;
+
+#[repr(u32)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Context {
+    Kernel = 1,
+    User = 2
+}
+
+impl TryFrom<u32> for Context {
+    type Error = Error;
+
+    fn try_from(value: u32) -> Result<Self, Self::Error> {
+        match value {
+            1 => Ok(Self::Kernel),
+            2 => Ok(Self::User),
+            _ => Err(Error::UnknownContext(value))
+        }
+    }
+}
+
+#[repr(u32)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Codec {
+    None = 0,
+    Lz4 = 1
+}
+
+impl TryFrom<u32> for Codec {
+    type Error = Error;
+
+    fn try_from(value: u32) -> Result<Self, Self::Error> {
+        match value {
+            0 => Ok(Self::None),
+            1 => Ok(Self::Lz4),
+            _ => Err(Error::UnknownCodec(value))
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Error {
+    InvalidElf,
+    SectionNotFound(&'static str),
+    NotRusset,
+    Corrupted,
+    Truncated,
+    BadNoteSize,
+    BadNoteName,
+    BadNoteType(u32),
+    UnknownVersion(u32),
+    UnknownContext(u32),
+    UnknownCodec(u32),
+    Decompress,
+    TooLarge,
+    BadString,
+    BadSymbolTable,
+    Untrusted,
+    BadSignature
+}
+
+pub(crate) struct Reader<'a> {
+    data: &'a [u8],
+    offset: usize
+}
+
+impl<'a> Reader<'a> {
+    fn new(data: &'a [u8]) -> Self {
+        Self { data, offset: 0 }
+    }
+
+    fn bytes(&mut self, len: usize) -> Result<&'a [u8], Error> {
+        let end = self.offset.checked_add(len).ok_or(Error::Truncated)?;
+        let bytes = self.data.get(self.offset..end).ok_or(Error::Truncated)?;
+        self.offset = end;
+        Ok(bytes)
+    }
+
+    fn u32(&mut self) -> Result<u32, Error> {
+        let bytes = self.bytes(4)?;
+        Ok(u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+    }
+
+    fn string(&mut self) -> Result<String, Error> {
+        let len = self.u32()? as usize;
+        String::from_utf8(self.bytes(len)?.to_vec()).map_err(|_| Error::BadString)
+    }
+}
+
+fn write_string(bytes: &mut Vec<u8>, string: &str) {
+    bytes.extend_from_slice(&(string.len() as u32).to_le_bytes());
+    bytes.extend_from_slice(string.as_bytes());
+}
+
+fn align4(len: usize) -> Option<usize> {
+    len.checked_add(3).map(|len| len & !3)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Note<'a> {
+    pub n_type: u32,
+    pub name: &'a [u8],
+    pub desc: &'a [u8]
+}
+
+impl<'a> Note<'a> {
+    pub fn new(n_type: u32, desc: &'a [u8]) -> Self {
+        Self { n_type, name: NOTE_NAME.as_bytes(), desc }
+    }
+
+    pub fn parse(data: &'a [u8]) -> Result<(Self, &'a [u8]), Error> {
+        let mut reader = Reader::new(data);
+        let name_len = reader.u32()? as usize;
+        let desc_len = reader.u32()? as usize;
+        let n_type = reader.u32()?;
+
+        let name = reader.bytes(align4(name_len).ok_or(Error::BadNoteSize)?)?;
+        let desc = reader.bytes(align4(desc_len).ok_or(Error::BadNoteSize)?)?;
+
+        let name = &name[..name_len];
+        let name = name.strip_suffix(&[0]).unwrap_or(name);
+
+        Ok((Self { n_type, name, desc: &desc[..desc_len] }, &data[reader.offset..]))
+    }
+
+    pub fn is_russet(&self) -> bool {
+        self.name == NOTE_NAME.as_bytes()
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let name_len = self.name.len() + 1;
+        let mut bytes = Vec::new();
+
+        bytes.extend_from_slice(&(name_len as u32).to_le_bytes());
+        bytes.extend_from_slice(&(self.desc.len() as u32).to_le_bytes());
+        bytes.extend_from_slice(&self.n_type.to_le_bytes());
+        bytes.extend_from_slice(self.name);
+        bytes.push(0);
+        bytes.resize(align4(bytes.len()).unwrap_or(bytes.len()), 0);
+        bytes.extend_from_slice(self.desc);
+        bytes.resize(align4(bytes.len()).unwrap_or(bytes.len()), 0);
+
+        bytes
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Header {
+    pub version: u32,
+    pub context: Context,
+    pub abi_version: u32,
+    pub checksum: u32,
+    pub codec: Codec,
+    pub size: u32
+}
+
+impl Header {
+    pub fn new(context: Context, abi_version: u32, codec: Codec, pe: &[u8]) -> Result<Self, Error> {
+        Ok(Self {
+            version: HEADER_VERSION,
+            context,
+            abi_version,
+            checksum: checksum(pe),
+            codec,
+            size: u32::try_from(pe.len()).map_err(|_| Error::TooLarge)?
+        })
+    }
+
+    pub fn size(version: u32) -> Option<usize> {
+        match version {
+            0 => Some(16),
+            1 => Some(24),
+            _ => None
+        }
+    }
+
+    pub fn decode(desc: &[u8]) -> Result<Self, Error> {
+        let mut reader = Reader::new(desc);
+        let version = reader.u32()?;
+
+        match Self::size(version) {
+            Some(size) if desc.len() < size => Err(Error::BadNoteSize),
+            Some(_) => Ok(Self {
+                version,
+                context: Context::try_from(reader.u32()?)?,
+                abi_version: reader.u32()?,
+                checksum: reader.u32()?,
+                codec: if version >= 1 { Codec::try_from(reader.u32()?)? } else { Codec::None },
+                size: if version >= 1 { reader.u32()? } else { 0 }
+            }),
+            None => Err(Error::UnknownVersion(version))
+        }
+    }
+
+    pub fn from_note(note: &Note) -> Result<Self, Error> {
+        if !note.is_russet() {
+            Err(Error::BadNoteName)
+        } else if note.n_type != NOTE_TYPE_HEADER {
+            Err(Error::BadNoteType(note.n_type))
+        } else {
+            Self::decode(note.desc)
+        }
+    }
+
+    pub fn encode(&self) -> Vec<u8> {
+        let mut bytes = Vec::new();
+
+        bytes.extend_from_slice(&self.version.to_le_bytes());
+        bytes.extend_from_slice(&(self.context as u32).to_le_bytes());
+        bytes.extend_from_slice(&self.abi_version.to_le_bytes());
+        bytes.extend_from_slice(&self.checksum.to_le_bytes());
+
+        if self.version >= 1 {
+            bytes.extend_from_slice(&(self.codec as u32).to_le_bytes());
+            bytes.extend_from_slice(&self.size.to_le_bytes());
+        }
+
+        bytes
+    }
+
+    pub fn to_note(&self) -> Vec<u8> {
+        Note::new(NOTE_TYPE_HEADER, &self.encode()).to_bytes()
+    }
+
+    pub fn verify(&self, pe: &[u8]) -> bool {
+        checksum(pe) == self.checksum
+    }
+
+    pub fn compress(&self, pe: &[u8]) -> Vec<u8> {
+        match self.codec {
+            Codec::None => pe.to_vec(),
+            Codec::Lz4 => lz4_flex::compress(pe)
+        }
+    }
+
+    pub fn decompress(&self, payload: &[u8]) -> Result<Vec<u8>, Error> {
+        match self.codec {
+            Codec::None => Ok(payload.to_vec()),
+            Codec::Lz4 if self.size as usize > payload.len().saturating_mul(MAX_LZ4_RATIO).min(MAX_SIZE) => Err(Error::Decompress),
+            Codec::Lz4 => {
+                let mut pe = vec![0; self.size as usize];
+                match lz4_flex::decompress_into(payload, &mut pe) {
+                    Ok(size) if size == pe.len() => Ok(pe),
+                    _ => Err(Error::Decompress)
+                }
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Signature {
+    pub public_key: [u8; 32],
+    pub signature: [u8; 64]
+}
+
+impl Signature {
+    pub const SIZE: usize = 96;
+
+    pub fn decode(desc: &[u8]) -> Result<Self, Error> {
+        if desc.len() != Self::SIZE {
+            return Err(Error::BadNoteSize);
+        }
+
+        let mut reader = Reader::new(desc);
+        let mut signature = Self { public_key: [0; 32], signature: [0; 64] };
+        signature.public_key.copy_from_slice(reader.bytes(32)?);
+        signature.signature.copy_from_slice(reader.bytes(64)?);
+
+        Ok(signature)
+    }
+
+    pub fn from_note(note: &Note) -> Result<Self, Error> {
+        if !note.is_russet() {
+            Err(Error::BadNoteName)
+        } else if note.n_type != NOTE_TYPE_SIGNATURE {
+            Err(Error::BadNoteType(note.n_type))
+        } else {
+            Self::decode(note.desc)
+        }
+    }
+
+    pub fn encode(&self) -> Vec<u8> {
+        let mut bytes = Vec::new();
+
+        bytes.extend_from_slice(&self.public_key);
+        bytes.extend_from_slice(&self.signature);
+
+        bytes
+    }
+
+    pub fn to_note(&self) -> Vec<u8> {
+        Note::new(NOTE_TYPE_SIGNATURE, &self.encode()).to_bytes()
+    }
+
+    pub fn message(header: &Header, payload: &[u8]) -> Vec<u8> {
+        let mut message = header.encode();
+        message.extend_from_slice(payload);
+        message
+    }
+
+    pub fn verify(&self, header: &Header, payload: &[u8], trusted_keys: &[[u8; 32]]) -> Result<(), Error> {
+        if !trusted_keys.contains(&self.public_key) {
+            return Err(Error::Untrusted);
+        }
+
+        let public_key = PublicKey::from_slice(&self.public_key).map_err(|_| Error::BadSignature)?;
+        let signature = ed25519_compact::Signature::from_slice(&self.signature).map_err(|_| Error::BadSignature)?;
+
+        let mut state = public_key.verify_incremental(&signature).map_err(|_| Error::BadSignature)?;
+        state.absorb(header.encode());
+        state.absorb(payload);
+        state.verify().map_err(|_| Error::BadSignature)
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Metadata {
+    pub name: String,
+    pub version: String,
+    pub description: String,
+    pub authors: Vec<String>
+}
+
+impl Metadata {
+    pub fn decode(desc: &[u8]) -> Result<Self, Error> {
+        let mut reader = Reader::new(desc);
+        let mut metadata = Self {
+            name: reader.string()?,
+            version: reader.string()?,
+            description: reader.string()?,
+            authors: Vec::new()
+        };
+
+        let authors = reader.u32()?;
+        for _ in 0..authors {
+            metadata.authors.push(reader.string()?);
+        }
+
+        Ok(metadata)
+    }
+
+    pub fn from_note(note: &Note) -> Result<Self, Error> {
+        if !note.is_russet() {
+            Err(Error::BadNoteName)
+        } else if note.n_type != NOTE_TYPE_METADATA {
+            Err(Error::BadNoteType(note.n_type))
+        } else {
+            Self::decode(note.desc)
+        }
+    }
+
+    pub fn encode(&self) -> Vec<u8> {
+        let mut bytes = Vec::new();
+
+        write_string(&mut bytes, &self.name);
+        write_string(&mut bytes, &self.version);
+        write_string(&mut bytes, &self.description);
+        bytes.extend_from_slice(&(self.authors.len() as u32).to_le_bytes());
+        for author in &self.authors {
+            write_string(&mut bytes, author);
+        }
+
+        bytes
+    }
+
+    pub fn to_note(&self) -> Vec<u8> {
+        Note::new(NOTE_TYPE_METADATA, &self.encode()).to_bytes()
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct SymbolTable<'a> {
+    entries: &'a [u8],
+    strings: &'a [u8]
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Symbol<'a> {
+    pub name: &'a str,
+    pub rva: u32,
+    pub size: u32
+}
+
+impl<'a> SymbolTable<'a> {
+    pub const VERSION: u32 = 1;
+    const ENTRY_SIZE: usize = 16;
+
+    pub fn parse(data: &'a [u8]) -> Result<Self, Error> {
+        let mut reader = Reader::new(data);
+        if reader.u32()? != Self::VERSION {
+            return Err(Error::BadSymbolTable);
+        }
+
+        let count = reader.u32()? as usize;
+        let entries = reader.bytes(count.checked_mul(Self::ENTRY_SIZE).ok_or(Error::BadSymbolTable)?)?;
+        let strings = &data[reader.offset..];
+
+        Ok(Self { entries, strings })
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len() / Self::ENTRY_SIZE
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn get(&self, index: usize) -> Option<Symbol<'a>> {
+        let offset = index.checked_mul(Self::ENTRY_SIZE)?;
+        let mut reader = Reader::new(self.entries.get(offset..offset + Self::ENTRY_SIZE)?);
+
+        let rva = reader.u32().ok()?;
+        let size = reader.u32().ok()?;
+        let name_offset = reader.u32().ok()? as usize;
+        let name_len = reader.u32().ok()? as usize;
+
+        let name = self.strings.get(name_offset..name_offset.checked_add(name_len)?)?;
+        Some(Symbol { name: core::str::from_utf8(name).ok()?, rva, size })
+    }
+
+    pub fn lookup(&self, rva: u32) -> Option<Symbol<'a>> {
+        let (mut low, mut high) = (0, self.len());
+
+        while low < high {
+            let middle = low + (high - low) / 2;
+            if self.get(middle)?.rva <= rva {
+                low = middle + 1;
+            } else {
+                high = middle;
+            }
+        }
+
+        let symbol = self.get(low.checked_sub(1)?)?;
+        if symbol.size == 0 || rva - symbol.rva < symbol.size {
+            Some(symbol)
+        } else {
+            None
+        }
+    }
+
+    pub fn encode(symbols: &[Symbol]) -> Vec<u8> {
+        let mut symbols = symbols.to_vec();
+        symbols.sort_by_key(|symbol| symbol.rva);
+
+        let mut entries = Vec::new();
+        let mut strings = Vec::new();
+
+        for symbol in symbols {
+            entries.extend_from_slice(&symbol.rva.to_le_bytes());
+            entries.extend_from_slice(&symbol.size.to_le_bytes());
+            entries.extend_from_slice(&(strings.len() as u32).to_le_bytes());
+            entries.extend_from_slice(&(symbol.name.len() as u32).to_le_bytes());
+            strings.extend_from_slice(symbol.name.as_bytes());
+        }
+
+        let mut bytes = Vec::new();
+        bytes.extend_from_slice(&Self::VERSION.to_le_bytes());
+        bytes.extend_from_slice(&((entries.len() / Self::ENTRY_SIZE) as u32).to_le_bytes());
+        bytes.extend_from_slice(&entries);
+        bytes.extend_from_slice(&strings);
+
+        bytes
+    }
+}
+
+pub fn checksum(data: &[u8]) -> u32 {
+    let crc: crc::Crc<u32> = crc::Crc::<u32>::new(&crc::CRC_32_BZIP2);
+    crc.checksum(data)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ed25519_compact::{KeyPair, Seed};
+
+    const PE: &[u8] = b"MZ\x90\x00 a small stand-in for a PE image, repeated repeated repeated repeated";
+
+    fn key(seed: u8) -> KeyPair {
+        KeyPair::from_seed(Seed::new([seed; 32]))
+    }
+
+    fn sign(pair: &KeyPair, header: &Header, payload: &[u8]) -> Signature {
+        Signature {
+            public_key: *pair.pk,
+            signature: *pair.sk.sign(Signature::message(header, payload), None)
+        }
+    }
+
+    #[test]
+    fn note_round_trip() {
+        let bytes = Note::new(NOTE_TYPE_METADATA, b"hello").to_bytes();
+        assert_eq!(bytes.len() % 4, 0);
+
+        let (note, rest) = Note::parse(&bytes).unwrap();
+        assert!(note.is_russet());
+        assert_eq!(note.n_type, NOTE_TYPE_METADATA);
+        assert_eq!(note.desc, b"hello");
+        assert!(rest.is_empty());
+    }
+
+    #[test]
+    fn note_parse_returns_rest() {
+        let mut bytes = Note::new(NOTE_TYPE_HEADER, b"first").to_bytes();
+        bytes.extend_from_slice(&Note::new(NOTE_TYPE_SIGNATURE, b"second").to_bytes());
+
+        let (first, rest) = Note::parse(&bytes).unwrap();
+        let (second, rest) = Note::parse(rest).unwrap();
+        assert_eq!((first.n_type, first.desc), (NOTE_TYPE_HEADER, &b"first"[..]));
+        assert_eq!((second.n_type, second.desc), (NOTE_TYPE_SIGNATURE, &b"second"[..]));
+        assert!(rest.is_empty());
+    }
+
+    #[test]
+    fn note_parse_rejects_truncated_data() {
+        let bytes = Note::new(NOTE_TYPE_HEADER, b"payload").to_bytes();
+        assert_eq!(Note::parse(&bytes[..8]), Err(Error::Truncated));
+        assert_eq!(Note::parse(&bytes[..bytes.len() - 4]), Err(Error::Truncated));
+
+        let mut oversized = bytes.clone();
+        oversized[4..8].copy_from_slice(&u32::MAX.to_le_bytes());
+        assert!(Note::parse(&oversized).is_err());
+    }
+
+    #[test]
+    fn header_round_trip() {
+        let header = Header::new(Context::User, ABI_VERSION, Codec::Lz4, PE).unwrap();
+        let note = header.to_note();
+        let (note, _) = Note::parse(&note).unwrap();
+
+        assert_eq!(Header::from_note(&note), Ok(header));
+        assert_eq!(header.size as usize, PE.len());
+        assert!(header.verify(PE));
+        assert!(!header.verify(&PE[1..]));
+    }
+
+    #[test]
+    fn header_decodes_version_zero() {
+        let mut desc = Vec::new();
+        for value in [0, Context::Kernel as u32, 1, 0x1234_5678] {
+            desc.extend_from_slice(&value.to_le_bytes());
+        }
+
+        let header = Header::decode(&desc).unwrap();
+        assert_eq!((header.version, header.context, header.codec, header.checksum), (0, Context::Kernel, Codec::None, 0x1234_5678));
+        assert_eq!(header.encode(), desc);
+    }
+
+    #[test]
+    fn header_rejects_bad_fields() {
+        let header = Header::new(Context::User, ABI_VERSION, Codec::None, PE).unwrap().encode();
+        assert_eq!(Header::decode(&header[..20]), Err(Error::BadNoteSize));
+
+        let mut version = header.clone();
+        version[..4].copy_from_slice(&7u32.to_le_bytes());
+        assert_eq!(Header::decode(&version), Err(Error::UnknownVersion(7)));
+
+        let mut context = header.clone();
+        context[4..8].copy_from_slice(&9u32.to_le_bytes());
+        assert_eq!(Header::decode(&context), Err(Error::UnknownContext(9)));
+
+        let mut codec = header;
+        codec[16..20].copy_from_slice(&3u32.to_le_bytes());
+        assert_eq!(Header::decode(&codec), Err(Error::UnknownCodec(3)));
+
+        let note = Note::new(NOTE_TYPE_METADATA, &[]);
+        assert_eq!(Header::from_note(&note), Err(Error::BadNoteType(NOTE_TYPE_METADATA)));
+    }
+
+    #[test]
+    fn compression_round_trip() {
+        for codec in [Codec::None, Codec::Lz4] {
+            let header = Header::new(Context::Kernel, ABI_VERSION, codec, PE).unwrap();
+            let payload = header.compress(PE);
+            assert_eq!(header.decompress(&payload).unwrap(), PE);
+        }
+    }
+
+    #[test]
+    fn decompress_rejects_wrong_size() {
+        let mut header = Header::new(Context::Kernel, ABI_VERSION, Codec::Lz4, PE).unwrap();
+        let payload = header.compress(PE);
+        header.size += 1;
+        assert_eq!(header.decompress(&payload), Err(Error::Decompress));
+    }
+
+    #[test]
+    fn decompress_rejects_oversized_header() {
+        let mut header = Header::new(Context::Kernel, ABI_VERSION, Codec::Lz4, PE).unwrap();
+        let payload = vec![0; MAX_SIZE / MAX_LZ4_RATIO + 1];
+        header.size = MAX_SIZE as u32 + 1;
+        assert_eq!(header.decompress(&payload), Err(Error::Decompress));
+    }
+
+    #[test]
+    fn metadata_round_trip() {
+        let metadata = Metadata {
+            name: String::from("demo"),
+            version: String::from("1.2.3"),
+            description: String::from("A demo program"),
+            authors: vec![String::from("One"), String::from("Two")]
+        };
+
+        let note = metadata.to_note();
+        let (note, _) = Note::parse(&note).unwrap();
+        assert_eq!(Metadata::from_note(&note), Ok(metadata.clone()));
+        assert_eq!(Metadata::decode(&metadata.encode()[..10]), Err(Error::Truncated));
+    }
+
+    #[test]
+    fn symbol_lookup() {
+        let symbols = [
+            Symbol { name: "second", rva: 0x2000, size: 0x10 },
+            Symbol { name: "first", rva: 0x1000, size: 0x100 },
+            Symbol { name: "open", rva: 0x3000, size: 0 }
+        ];
+        let bytes = SymbolTable::encode(&symbols);
+        let table = SymbolTable::parse(&bytes).unwrap();
+
+        assert_eq!(table.len(), 3);
+        assert_eq!(table.lookup(0x1080).map(|symbol| symbol.name), Some("first"));
+        assert_eq!(table.lookup(0x1100), None);
+        assert_eq!(table.lookup(0x2008).map(|symbol| symbol.name), Some("second"));
+        assert_eq!(table.lookup(0x9000).map(|symbol| symbol.name), Some("open"));
+        assert_eq!(table.lookup(0x0fff), None);
+        assert_eq!(SymbolTable::parse(&bytes[..12]).map(|table| table.len()), Err(Error::Truncated));
+    }
+
+    #[test]
+    fn signature_round_trip() {
+        let pair = key(1);
+        let header = Header::new(Context::User, ABI_VERSION, Codec::None, PE).unwrap();
+        let signature = sign(&pair, &header, PE);
+
+        let note = signature.to_note();
+        let (note, _) = Note::parse(&note).unwrap();
+        assert_eq!(Signature::from_note(&note), Ok(signature));
+        assert_eq!(Signature::decode(&signature.encode()[1..]), Err(Error::BadNoteSize));
+    }
+
+    #[test]
+    fn signature_verify_with_trusted_key() {
+        let pair = key(1);
+        let header = Header::new(Context::User, ABI_VERSION, Codec::None, PE).unwrap();
+        let signature = sign(&pair, &header, PE);
+
+        assert_eq!(signature.verify(&header, PE, &[*key(2).pk, *pair.pk]), Ok(()));
+    }
+
+    #[test]
+    fn signature_verify_rejects_wrong_key() {
+        let header = Header::new(Context::User, ABI_VERSION, Codec::None, PE).unwrap();
+        let signature = sign(&key(1), &header, PE);
+
+        assert_eq!(signature.verify(&header, PE, &[*key(2).pk]), Err(Error::Untrusted));
+        assert_eq!(signature.verify(&header, PE, &[]), Err(Error::Untrusted));
+
+        let forged = Signature { public_key: *key(2).pk, ..signature };
+        assert_eq!(forged.verify(&header, PE, &[*key(2).pk]), Err(Error::BadSignature));
+    }
+
+    #[test]
+    fn signature_verify_rejects_tampered_payload() {
+        let pair = key(1);
+        let header = Header::new(Context::User, ABI_VERSION, Codec::None, PE).unwrap();
+        let signature = sign(&pair, &header, PE);
+
+        let mut payload = PE.to_vec();
+        payload[4] ^= 1;
+        assert_eq!(signature.verify(&header, &payload, &[*pair.pk]), Err(Error::BadSignature));
+
+        let tampered = Header { abi_version: ABI_VERSION + 1, ..header };
+        assert_eq!(signature.verify(&tampered, PE, &[*pair.pk]), Err(Error::BadSignature));
+    }
+}
@@ -0,0 +1,52 @@
+use std::fs;
+use std::path::Path;
+use relf::{Bundle, Error};
+
+const EXPECTED: &[(&str, Option<Error>)] = &[
+    ("bad-checksum", Some(Error::Corrupted)),
+    ("empty", Some(Error::InvalidElf)),
+    ("garbage", Some(Error::InvalidElf)),
+    ("header-v0-truncated", Some(Error::BadNoteSize)),
+    ("lz4-corrupted", Some(Error::Corrupted)),
+    ("lz4-size-bomb", Some(Error::Decompress)),
+    ("lz4-size-short", Some(Error::Decompress)),
+    ("metadata-string-overflow", Some(Error::Truncated)),
+    ("missing-header", Some(Error::NotRusset)),
+    ("missing-note-section", Some(Error::NotRusset)),
+    ("not-elf", Some(Error::InvalidElf)),
+    ("note-bad-name", Some(Error::NotRusset)),
+    ("note-descsz-huge", Some(Error::Truncated)),
+    ("note-descsz-short", Some(Error::BadNoteSize)),
+    ("note-namesz-huge", Some(Error::Truncated)),
+    ("note-namesz-zero", Some(Error::Truncated)),
+    ("payload-compressed-section", Some(Error::InvalidElf)),
+    ("payload-offset-out-of-range", Some(Error::InvalidElf)),
+    ("payload-size-out-of-range", Some(Error::InvalidElf)),
+    ("section-table-out-of-range", Some(Error::InvalidElf)),
+    ("signature-short", Some(Error::BadNoteSize)),
+    ("truncated-elf-header", Some(Error::InvalidElf)),
+    ("truncated-notes", Some(Error::InvalidElf)),
+    ("truncated-payload", Some(Error::InvalidElf)),
+    ("unknown-codec", Some(Error::UnknownCodec(5))),
+    ("unknown-context", Some(Error::UnknownContext(7))),
+    ("unknown-version", Some(Error::UnknownVersion(9))),
+    ("valid", None),
+    ("valid-lz4", None),
+    ("valid-signed", None)
+];
+
+#[test]
+fn corpus() {
+    let corpus = Path::new(env!("CARGO_MANIFEST_DIR")).join("../../../tools/fuzz/corpus/parse_bundle");
+    let mut names: Vec<String> = fs::read_dir(&corpus).unwrap()
+        .map(|entry| entry.unwrap().file_name().to_string_lossy().into_owned())
+        .collect();
+    names.sort();
+    assert_eq!(names, EXPECTED.iter().map(|(name, _)| *name).collect::<Vec<_>>(), "corpus and expectations differ");
+
+    for (name, expected) in EXPECTED {
+        let data = fs::read(corpus.join(name)).unwrap();
+        let result = Bundle::parse(&data).and_then(|bundle| bundle.pe().map(|_| ()));
+        assert_eq!(result.err(), *expected, "{name}");
+    }
+}
@@ -9,15 +9,30 @@ pub fn russet_entry(_args: TokenStream, input: TokenStream) -> TokenStream {
     let name = parsed.sig.ident;
     let input: proc_macro2::TokenStream = input.into();
 
+    let hosted = if name == "main" {
+        quote! {}
+    } else {
+        quote! {
+            #[cfg(not(target_os = "uefi"))]
+            fn main() {
+                #name();
+            }
+        }
+    };
+
     let out = quote! {
+        #[cfg(target_os = "uefi")]
         use uefi::prelude::*;
         #input
 
+        #[cfg(target_os = "uefi")]
         #[entry] fn __russet_main(_image: Handle, mut system_table: SystemTable<Boot>) -> Status {
             unsafe { rstd::init(system_table, _image); }
             #name();
             Status::SUCCESS
         }
+
+        #hosted
     };
 
     out.into()
@@ -0,0 +1,54 @@
+use alloc::collections::BTreeMap;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use crate::sys;
+
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Arguments {
+    pub command: String,
+    pub args: BTreeMap<String, Option<String>>,
+    pub names: Vec<String>
+}
+
+impl Arguments {
+    pub fn parse<I: IntoIterator<Item = String>>(command: String, args: I) -> Self {
+        let mut arguments = Self { command, ..Self::default() };
+
+        for arg in args {
+            if let Some(arg) = arg.strip_prefix("--") {
+                match arg.split_once('=') {
+                    Some((name, value)) => arguments.args.insert(name.to_string(), Some(value.to_string())),
+                    None => arguments.args.insert(arg.to_string(), None)
+                };
+            } else if let Some(flags) = arg.strip_prefix('-') {
+                for flag in flags.chars() {
+                    arguments.args.insert(flag.to_string(), None);
+                }
+            } else {
+                arguments.names.push(arg);
+            }
+        }
+
+        arguments
+    }
+
+    pub fn has(&self, name: &str) -> bool {
+        self.args.contains_key(name)
+    }
+
+    pub fn value(&self, name: &str) -> Option<&str> {
+        self.args.get(name)?.as_deref()
+    }
+}
+
+pub fn args() -> Arguments {
+    sys::args()
+}
+
+pub fn current_dir() -> String {
+    sys::current_dir()
+}
+
+pub fn set_current_dir(path: &str) -> bool {
+    sys::set_current_dir(path)
+}
@@ -0,0 +1,39 @@
+use alloc::string::String;
+use alloc::vec::Vec;
+use crate::sys;
+
+pub fn read(path: &str) -> Option<Vec<u8>> {
+    sys::read(path)
+}
+
+pub fn read_to_string(path: &str) -> Option<String> {
+    String::from_utf8(read(path)?).ok()
+}
+
+pub fn write<C: AsRef<[u8]>>(path: &str, contents: C) -> bool {
+    sys::write(path, contents.as_ref())
+}
+
+pub fn exists(path: &str) -> bool {
+    sys::exists(path)
+}
+
+pub fn is_dir(path: &str) -> bool {
+    sys::is_dir(path)
+}
+
+pub fn is_file(path: &str) -> bool {
+    sys::is_file(path)
+}
+
+pub fn create_dir(path: &str) -> bool {
+    sys::create_dir(path)
+}
+
+pub fn remove_file(path: &str) -> bool {
+    sys::remove_file(path)
+}
+
+pub fn remove_dir(path: &str) -> bool {
+    sys::remove_dir(path)
+}
@@ -0,0 +1,6 @@
+use alloc::string::String;
+use crate::sys;
+
+pub fn readline() -> String {
+    sys::readline()
+}
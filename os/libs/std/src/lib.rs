@@ -1,13 +1,25 @@
-#![no_std]
+#![cfg_attr(target_os = "uefi", no_std)]
 
 pub extern crate alloc;
 
-use uefi::prelude::{Boot, SystemTable};
-use russet_common::CoreServices;
-
 pub mod prelude;
+pub mod env;
+pub mod fs;
+pub mod io;
+pub mod vars;
 mod macros;
 
+#[cfg(target_os = "uefi")]
+#[path = "sys/uefi.rs"]
+mod sys;
+
+#[cfg(not(target_os = "uefi"))]
+#[path = "sys/hosted.rs"]
+mod sys;
+
+#[cfg(target_os = "uefi")]
+pub use sys::init;
+
 pub use alloc::boxed;
 pub use alloc::borrow;
 pub use core::char;
@@ -69,8 +81,10 @@ pub use core::line;
 pub use core::matches;
 pub use core::module_path;
 pub use core::option_env;
-pub use uefi::print;
-pub use uefi::println;
+#[cfg(target_os = "uefi")]
+pub use russet_common::{print, println};
+#[cfg(not(target_os = "uefi"))]
+pub use std::{dbg, eprint, eprintln, print, println};
 pub use core::stringify;
 pub use core::todo;
 pub use core::unimplemented;
@@ -80,16 +94,3 @@ pub use core::writeln;
 
 #[allow(unused_imports)]
 pub use crate::macros::*;
-
-use alloc::string::String;
-use uefi::Handle;
-
-#[allow(dead_code)]
-pub(crate) static mut CORE_SERVICES: Option<CoreServices> = None;
-
-#[allow(clippy::missing_safety_doc)]
-pub unsafe fn init(mut system_table: SystemTable<Boot>, image: Handle) {
-    uefi::helpers::init(&mut system_table).unwrap();
-    let mut core = CoreServices::init(system_table, false);
-    core.transfer_system_table(image, String::new());
-}
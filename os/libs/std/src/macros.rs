@@ -1,13 +1,16 @@
+#[cfg(target_os = "uefi")]
 #[macro_export]
 macro_rules! eprintln {
     ($($arg:tt)*) => ($crate::println!($($arg)*));
 }
 
+#[cfg(target_os = "uefi")]
 #[macro_export]
 macro_rules! eprint {
     ($($arg:tt)*) => ($crate::print!($($arg)*));
 }
 
+#[cfg(target_os = "uefi")]
 #[macro_export]
 macro_rules! dbg {
     () => {
@@ -1,4 +1,4 @@
-pub use uefi::{println, print};
+pub use crate::{println, print};
 pub use rstd_entry::russet_entry;
 pub use crate::{eprint, eprintln};
 pub use core::prelude::*;
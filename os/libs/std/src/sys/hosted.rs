@@ -0,0 +1,158 @@
+use std::ffi::OsString;
+use std::fs;
+use std::io::{self, BufRead};
+use std::os::unix::ffi::{OsStrExt, OsStringExt};
+use std::path::PathBuf;
+use std::sync::Mutex;
+use crate::env::Arguments;
+
+static CURRENT_DIR: Mutex<Option<String>> = Mutex::new(None);
+
+fn root() -> PathBuf {
+    std::env::var_os("RUSSET_ROOT").map(PathBuf::from).unwrap_or(PathBuf::from("."))
+}
+
+fn normalize(cwd: &str, path: &str) -> String {
+    let mut parts: Vec<&str> = if path.starts_with('/') { Vec::new() } else { cwd.split('/').collect() };
+
+    for part in path.split('/') {
+        match part {
+            "" | "." => (),
+            ".." => {
+                parts.pop();
+            },
+            part => parts.push(part)
+        }
+    }
+
+    let path = parts.into_iter().filter(|part| !part.is_empty()).collect::<Vec<_>>().join("/");
+    format!("/{path}")
+}
+
+fn host_path(path: &str) -> PathBuf {
+    root().join(normalize(&current_dir(), path).trim_start_matches('/'))
+}
+
+pub fn readline() -> String {
+    let mut line = String::new();
+    let _ = io::stdin().lock().read_line(&mut line);
+    line.trim_end_matches(['\r', '\n']).to_string()
+}
+
+pub fn args() -> Arguments {
+    let mut args = std::env::args();
+    Arguments::parse(args.next().unwrap_or_default(), args)
+}
+
+pub fn current_dir() -> String {
+    CURRENT_DIR.lock().unwrap().clone()
+        .or_else(|| std::env::var("RUSSET_CWD").ok())
+        .unwrap_or(String::from("/"))
+}
+
+pub fn set_current_dir(path: &str) -> bool {
+    let path = normalize(&current_dir(), path);
+
+    if root().join(path.trim_start_matches('/')).is_dir() {
+        *CURRENT_DIR.lock().unwrap() = Some(path);
+        true
+    } else {
+        false
+    }
+}
+
+pub fn read(path: &str) -> Option<Vec<u8>> {
+    fs::read(host_path(path)).ok()
+}
+
+pub fn write(path: &str, data: &[u8]) -> bool {
+    fs::write(host_path(path), data).is_ok()
+}
+
+pub fn exists(path: &str) -> bool {
+    host_path(path).exists()
+}
+
+pub fn is_dir(path: &str) -> bool {
+    host_path(path).is_dir()
+}
+
+pub fn is_file(path: &str) -> bool {
+    host_path(path).is_file()
+}
+
+pub fn create_dir(path: &str) -> bool {
+    fs::create_dir(host_path(path)).is_ok()
+}
+
+pub fn remove_file(path: &str) -> bool {
+    fs::remove_file(host_path(path)).is_ok()
+}
+
+pub fn remove_dir(path: &str) -> bool {
+    fs::remove_dir(host_path(path)).is_ok()
+}
+
+fn valid_variable(name: &str) -> bool {
+    !name.is_empty() && !name.contains(['=', '\0'])
+}
+
+pub fn get_variable(name: &str) -> Option<Vec<u8>> {
+    if !valid_variable(name) {
+        return None;
+    }
+
+    std::env::var_os(name).map(OsString::into_vec)
+}
+
+pub fn set_variable(name: &str, value: &[u8]) -> bool {
+    if !valid_variable(name) || value.contains(&0) {
+        return false;
+    }
+
+    std::env::set_var(name, std::ffi::OsStr::from_bytes(value));
+    true
+}
+
+pub fn delete_variable(name: &str) -> bool {
+    if get_variable(name).is_none() {
+        return false;
+    }
+
+    std::env::remove_var(name);
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn normalize_paths() {
+        assert_eq!(normalize("/", "User"), "/User");
+        assert_eq!(normalize("/User", "Documents/./notes"), "/User/Documents/notes");
+        assert_eq!(normalize("/User/Documents", "../.."), "/");
+        assert_eq!(normalize("/User", "../../.."), "/");
+        assert_eq!(normalize("/User", "/System//Programs/"), "/System/Programs");
+    }
+
+    #[test]
+    fn parse_arguments() {
+        let arguments = Arguments::parse(String::from("Demo"), ["--name=value", "-ab", "--flag", "first", "second"].map(String::from));
+
+        assert_eq!(arguments.command, "Demo");
+        assert_eq!(arguments.value("name"), Some("value"));
+        assert!(arguments.has("a") && arguments.has("b") && arguments.has("flag"));
+        assert_eq!(arguments.value("flag"), None);
+        assert_eq!(arguments.names, ["first", "second"]);
+    }
+
+    #[test]
+    fn shared_variables() {
+        assert!(set_variable("Russet.Test", b"value"));
+        assert_eq!(get_variable("Russet.Test").as_deref(), Some(&b"value"[..]));
+        assert!(delete_variable("Russet.Test"));
+        assert_eq!(get_variable("Russet.Test"), None);
+        assert!(!set_variable("Russet=Test", b"value"));
+    }
+}
@@ -0,0 +1,155 @@
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+use uefi::allocator::Allocator;
+use uefi::prelude::{Boot, SystemTable};
+use uefi::Handle;
+use russet_common::CoreServices;
+use crate::env::Arguments;
+
+#[global_allocator]
+static ALLOCATOR: Allocator = Allocator;
+
+static mut CORE_SERVICES: Option<CoreServices> = None;
+
+struct Reader<'a>(&'a [u8]);
+
+impl<'a> Reader<'a> {
+    fn bytes(&mut self, len: usize) -> Option<&'a [u8]> {
+        if self.0.len() < len {
+            return None;
+        }
+
+        let (bytes, rest) = self.0.split_at(len);
+        self.0 = rest;
+        Some(bytes)
+    }
+
+    fn number(&mut self) -> Option<usize> {
+        Some(u64::from_le_bytes(self.bytes(8)?.try_into().ok()?) as usize)
+    }
+
+    fn string(&mut self) -> Option<String> {
+        let len = self.number()?;
+        String::from_utf8(self.bytes(len)?.to_vec()).ok()
+    }
+}
+
+#[allow(static_mut_refs)]
+fn core() -> &'static mut CoreServices {
+    unsafe { CORE_SERVICES.as_mut().expect("rstd::init was not called") }
+}
+
+fn real_path(path: &str) -> String {
+    let core = core();
+    let path = match path.strip_prefix('/') {
+        Some(path) => core.fs.resolve_path(&format!("/rootfs/{path}")),
+        None => core.fs.resolve_path(path)
+    };
+
+    if path == "\\rootfs" || path.starts_with("\\rootfs\\") {
+        path
+    } else {
+        String::from("\\rootfs")
+    }
+}
+
+fn decode_arguments(data: &[u8]) -> Option<Arguments> {
+    let mut reader = Reader(data);
+    let mut arguments = Arguments { command: reader.string()?, ..Arguments::default() };
+
+    for _ in 0..reader.number()? {
+        let kind = reader.bytes(1)?[0];
+        let name = reader.string()?;
+        let value = if kind == 1 { Some(reader.string()?) } else { None };
+        arguments.args.insert(name, value);
+    }
+
+    for _ in 0..reader.number()? {
+        arguments.names.push(reader.string()?);
+    }
+
+    Some(arguments)
+}
+
+#[allow(clippy::missing_safety_doc)]
+pub unsafe fn init(mut system_table: SystemTable<Boot>, image: Handle) {
+    uefi::helpers::init(&mut system_table).unwrap();
+    let mut core = CoreServices::init(system_table, false);
+    core.transfer_system_table(image, String::new());
+
+    if let Some(cwd) = core.get_shared_variable("cwd").ok().and_then(|(cwd, _)| String::from_utf8(cwd).ok()) {
+        let _ = core.fs.chdir(&cwd);
+    }
+
+    CORE_SERVICES = Some(core);
+}
+
+pub fn readline() -> String {
+    core().readline()
+}
+
+pub fn args() -> Arguments {
+    get_variable("argv")
+        .and_then(|data| decode_arguments(&data))
+        .unwrap_or_default()
+}
+
+pub fn current_dir() -> String {
+    core().fs.get_cwd()
+}
+
+pub fn set_current_dir(path: &str) -> bool {
+    let path = real_path(path);
+    is_dir_real(&path) && core().fs.chdir(&path).is_ok()
+}
+
+fn is_dir_real(path: &str) -> bool {
+    core().fs.file_exists(path) && core().fs.is_dir(path)
+}
+
+pub fn read(path: &str) -> Option<Vec<u8>> {
+    core().fs.read_bytes(&real_path(path))
+}
+
+pub fn write(path: &str, data: &[u8]) -> bool {
+    core().fs.write_bytes(&real_path(path), data)
+}
+
+pub fn exists(path: &str) -> bool {
+    core().fs.file_exists(&real_path(path))
+}
+
+pub fn is_dir(path: &str) -> bool {
+    is_dir_real(&real_path(path))
+}
+
+pub fn is_file(path: &str) -> bool {
+    let path = real_path(path);
+    core().fs.file_exists(&path) && core().fs.is_file(&path)
+}
+
+pub fn create_dir(path: &str) -> bool {
+    let path = real_path(path);
+    !core().fs.file_exists(&path) && core().fs.mkdir(&path)
+}
+
+pub fn remove_file(path: &str) -> bool {
+    core().fs.unlink(&real_path(path))
+}
+
+pub fn remove_dir(path: &str) -> bool {
+    core().fs.rmdir(&real_path(path))
+}
+
+pub fn get_variable(name: &str) -> Option<Vec<u8>> {
+    core().get_shared_variable(name).ok().map(|(data, _)| data)
+}
+
+pub fn set_variable(name: &str, value: &[u8]) -> bool {
+    core().set_shared_variable(name, value).is_ok()
+}
+
+pub fn delete_variable(name: &str) -> bool {
+    core().delete_shared_variable(name).is_ok()
+}
@@ -0,0 +1,14 @@
+use alloc::vec::Vec;
+use crate::sys;
+
+pub fn get(name: &str) -> Option<Vec<u8>> {
+    sys::get_variable(name)
+}
+
+pub fn set(name: &str, value: &[u8]) -> bool {
+    sys::set_variable(name, value)
+}
+
+pub fn delete(name: &str) -> bool {
+    sys::delete_variable(name)
+}
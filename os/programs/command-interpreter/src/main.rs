@@ -1,16 +1,152 @@
 #![no_main]
 #![no_std]
 
+use alloc::format;
 use alloc::string::{String, ToString};
 use alloc::vec;
+use alloc::vec::Vec;
+use log::Level;
 use uefi::prelude::*;
-use uefi::{print, println, CStr16};
+use uefi::allocator::Allocator;
+use uefi::CStr16;
+use russet_common::{print, println};
 use uefi::fs::PathBuf;
-use russet_common::{status_to_text, CoreServices, ExecBinaryError};
+use russet_common::{status_to_text, CoreServices, ExecBinaryError, STOP_EXIT_STATUS};
+use russet_common::boot::KernelOptions;
+use russet_common::journal;
 use russet_common::parser::Command;
 
 extern crate alloc;
 
+#[global_allocator]
+static ALLOCATOR: Allocator = Allocator;
+
+const MIB: u64 = 1024 * 1024;
+
+fn command_path(core: &CoreServices, name: &str) -> PathBuf {
+    let mut path: PathBuf = PathBuf::from(cstr16!("/rootfs/System/Programs"));
+
+    if name.starts_with("/") && name.len() > 1 {
+        let mut buf = vec![0; name.len() + 1];
+        path = PathBuf::from(cstr16!("/rootfs"));
+        path.push(PathBuf::from(CStr16::from_str_with_buf(&name[1..], &mut buf).unwrap()));
+    } else if name.starts_with("./") && name.len() > 2 {
+        let mut buf = vec![0; core.fs.get_real_cwd().len() + 1];
+        path = PathBuf::from(CStr16::from_str_with_buf(&core.fs.get_real_cwd(), &mut buf).unwrap());
+
+        let mut buf = vec![0; name.len() + 1];
+        path.push(PathBuf::from(CStr16::from_str_with_buf(name, &mut buf).unwrap()));
+    } else {
+        let mut buf = vec![0; name.len() + 1];
+        path.push(PathBuf::from(CStr16::from_str_with_buf(name, &mut buf).unwrap()));
+    }
+
+    path
+}
+
+fn display_path(path: &PathBuf) -> String {
+    let path = path.to_string();
+
+    match path.strip_prefix("\\rootfs") {
+        Some(path) if path.trim() == "" => String::from("/"),
+        Some(path) => String::from(path),
+        None => format!("//?{}", path.replace("\\", "/"))
+    }
+}
+
+fn print_timeline(core: &CoreServices) {
+    let Some(timeline) = core.boot_timeline() else {
+        println!("No boot timeline has been recorded.");
+        return;
+    };
+
+    println!("{:>15}  {:>15}  Event", "Elapsed", "Phase");
+    for phase in timeline.phases() {
+        println!("{:>9}.{:02} ms  {:>9}.{:02} ms  {}", phase.elapsed / 1000, phase.elapsed % 1000 / 10,
+            phase.duration / 1000, phase.duration % 1000 / 10, phase.label);
+    }
+}
+
+fn print_system_information(core: &mut CoreServices) {
+    let info = core.system_information();
+    let unknown = |value: &str| match value {
+        "" => String::from("unknown"),
+        value => String::from(value)
+    };
+
+    println!("{}", unknown(&info.os_string));
+    println!();
+    println!("Bootloader:  {}", unknown(&info.bootloader));
+    println!("Kernel:      {}", unknown(&info.kernel));
+    println!("Init:        {}", unknown(&info.init));
+    println!("Firmware:    {} {} (UEFI {})", info.firmware_vendor, info.firmware_revision, info.uefi_revision);
+    println!();
+
+    let cpu = &info.cpu;
+    println!("Processor:   {}", if cpu.brand.is_empty() { &cpu.vendor } else { &cpu.brand });
+    println!("             {} family {:#x} model {:#x} stepping {}", cpu.vendor, cpu.family, cpu.model, cpu.stepping);
+    if let Some(ref hypervisor) = cpu.hypervisor {
+        println!("Hypervisor:  {}", unknown(hypervisor));
+    }
+    println!("Features:    {}", cpu.features.join(" "));
+    println!();
+
+    match info.memory {
+        Some(memory) => {
+            println!("Memory:      {} MiB", memory.total / MIB);
+            println!("    Free:        {:>8} KiB", memory.free / 1024);
+            println!("    Boot:        {:>8} KiB", memory.boot / 1024);
+            println!("    Firmware:    {:>8} KiB", memory.firmware / 1024);
+            println!("    Reserved:    {:>8} KiB", memory.reserved / 1024);
+        },
+        None => println!("Memory:      unknown")
+    }
+    println!();
+
+    let console = &info.console;
+    println!("Console:     {} x {} characters", console.columns, console.rows);
+    if let Some((width, height)) = console.graphics {
+        println!("Graphics:    {width} x {height} pixels");
+    }
+    println!("Serial:      {:?}", console.serial);
+}
+
+fn print_journal(cmd: &Command) {
+    let level = match cmd.value("level").map(str::parse::<Level>) {
+        None => Level::Trace,
+        Some(Ok(level)) => level,
+        Some(Err(_)) => {
+            println!("The level must be one of error, warn, info, debug or trace.");
+            return;
+        }
+    };
+
+    let last = match cmd.value("last").map(str::parse::<usize>) {
+        None => usize::MAX,
+        Some(Ok(last)) => last,
+        Some(Err(_)) => {
+            println!("The number of records to show must be a number.");
+            return;
+        }
+    };
+
+    let source = cmd.value("source");
+    let entries = journal::entries();
+    if entries.is_empty() {
+        println!("No kernel log has been recorded.");
+        return;
+    }
+
+    let entries: Vec<_> = entries.into_iter()
+        .filter(|entry| entry.level <= level)
+        .filter(|entry| source.is_none_or(|source| entry.source.contains(source)))
+        .collect();
+
+    for entry in &entries[entries.len().saturating_sub(last)..] {
+        println!("{entry}");
+    }
+}
+
 #[entry]
 fn main(_image: Handle, mut system_table: SystemTable<Boot>) -> Status {
     uefi::helpers::init(&mut system_table).unwrap();
@@ -29,11 +165,23 @@ fn main(_image: Handle, mut system_table: SystemTable<Boot>) -> Status {
     }
 
     core.fs.chdir("\\rootfs\\User").expect("Failed to switch to /User");
+    core.close_timeline("command interpreter ready");
+
+    let options = core.command_line().map(|command| KernelOptions::from(&command)).unwrap_or_default();
+    if options.timing {
+        print_timeline(&core);
+    }
+
+    let mut slot = options.slot;
 
     loop {
         let pwd = core.fs.get_cwd();
         print!("\r\n{pwd}> ");
 
+        if let Some(slot) = slot.take() {
+            core.mark_boot_successful(slot);
+        }
+
         let cmd_str = &core.readline();
         if cmd_str.trim() == "" {
             continue;
@@ -50,19 +198,51 @@ fn main(_image: Handle, mut system_table: SystemTable<Boot>) -> Status {
                 "Exit" => {
                     return Status::SUCCESS;
                 },
+                "Shutdown" => {
+                    match cmd.names.as_slice() {
+                        [] => core.shutdown(0),
+                        [status] => match status.parse::<u8>() {
+                            Ok(status) if status < STOP_EXIT_STATUS => core.shutdown(status),
+                            _ => println!("The exit status must be a number between 0 and {}.", STOP_EXIT_STATUS - 1)
+                        },
+                        _ => println!("Invalid command use.")
+                    }
+                },
                 "_Crash" => {
                     core.execute_kmode_binary("/System/Kernel", true).expect("TODO: panic message");
                 },
                 "Print" => {
                     println!("{}", cmd.names.join(" "));
                 },
+                "GetBootTimeline" => {
+                    print_timeline(&core);
+                },
+                "GetSystemInformation" => {
+                    print_system_information(&mut core);
+                },
+                "GetSystemLog" => {
+                    if cmd.has("save") {
+                        if core.save_journal() {
+                            println!("The kernel log has been saved to /System/Logs/Journal.");
+                        } else {
+                            println!("The kernel log could not be saved.");
+                        }
+                    } else {
+                        print_journal(&cmd);
+                    }
+                },
                 "Help" => {
                     println!();
                     println!("Command interpreter built-in commands:");
-                    println!("    GetCurrentDirectory  - Show current work directory");
-                    println!("    Exit                 - Quit the current interpreter session");
-                    println!("    Print                - Display text on the console");
-                    println!("    GetCommandFile       - Show the file associated with an external command");
+                    println!("    GetCurrentDirectory   - Show current work directory");
+                    println!("    Exit                  - Quit the current interpreter session");
+                    println!("    Shutdown              - Turn off the computer");
+                    println!("    Print                 - Display text on the console");
+                    println!("    GetCommandFile        - Show the file associated with an external command");
+                    println!("    GetCommandInformation - Show the name, version and author of an external command");
+                    println!("    GetBootTimeline       - Show how long each phase of the last boot took");
+                    println!("    GetSystemInformation  - Show versions, firmware, processor, memory and console details");
+                    println!("    GetSystemLog          - Show the kernel log, filtered with --level, --source and --last");
                 },
                 "ChangeDirectory" => {
                     if cmd.names.len() == 1 {
@@ -90,59 +270,43 @@ fn main(_image: Handle, mut system_table: SystemTable<Boot>) -> Status {
                 },
                 "GetCommandFile" => {
                     for name in cmd.names {
-                        let mut path: PathBuf = PathBuf::from(cstr16!("/rootfs/System/Programs"));
-
-                        if name.starts_with("/") && name.len() > 1 {
-                            let mut buf = vec![0; name.len() + 1];
-                            path = PathBuf::from(cstr16!("/rootfs"));
-                            path.push(PathBuf::from(CStr16::from_str_with_buf(&name[1..], &mut buf).unwrap()));
-                        } else if name.starts_with("./") && name.len() > 2 {
-                            let mut buf = vec![0; core.fs.get_real_cwd().len() + 1];
-                            path = PathBuf::from(CStr16::from_str_with_buf(&core.fs.get_real_cwd(), &mut buf).unwrap());
-
-                            let mut buf = vec![0; name.len() + 1];
-                            path.push(PathBuf::from(CStr16::from_str_with_buf(&name, &mut buf).unwrap()));
-                        } else {
-                            let mut buf = vec![0; name.len() + 1];
-                            path.push(PathBuf::from(CStr16::from_str_with_buf(&name, &mut buf).unwrap()));
-                        }
+                        let path = command_path(&core, &name);
 
                         if core.fs.file_exists(&path.to_string()) && core.fs.is_file(&path.to_string()) {
-                            let path = path.to_string();
-                            if path.starts_with("\\rootfs") {
-                                if let Some(path) = path.strip_prefix("\\rootfs") {
-                                    println!("{}", if path.trim() == "" {
-                                        "/"
-                                    } else {
-                                        path
-                                    });
-                                }
-                            } else {
-                                println!("//?{}", path.replace("\\", "/"))
-                            }
+                            println!("{}", display_path(&path));
                         } else {
                             println!("The command \"{name}\" could not found.");
                         }
                     }
+                },
+                "GetCommandInformation" => {
+                    for name in cmd.names {
+                        let path = command_path(&core, &name);
+
+                        if !core.fs.file_exists(&path.to_string()) || !core.fs.is_file(&path.to_string()) {
+                            println!("The command \"{name}\" could not found.");
+                            continue;
+                        }
+
+                        match core.get_program_metadata(&path.to_string()) {
+                            Some(metadata) => {
+                                println!("{} {}", metadata.name, metadata.version);
+                                if !metadata.description.is_empty() {
+                                    println!("    {}", metadata.description);
+                                }
+                                if !metadata.authors.is_empty() {
+                                    println!("    Authors: {}", metadata.authors.join(", "));
+                                }
+                                println!("    File: {}", display_path(&path));
+                            },
+                            None => println!("No information is available for \"{name}\".")
+                        }
+                    }
                 }
                 _ => {
                     core.set_shared_variable("argv", cmd.to_bytes().as_slice()).unwrap();
-                    let mut path: PathBuf = PathBuf::from(cstr16!("/rootfs/System/Programs"));
-
-                    if cmd.command.starts_with("/") && cmd.command.len() > 1 {
-                        let mut buf = vec![0; cmd.command.len() + 1];
-                        path = PathBuf::from(cstr16!("/rootfs"));
-                        path.push(PathBuf::from(CStr16::from_str_with_buf(&cmd.command[1..], &mut buf).unwrap()));
-                    } else if cmd.command.starts_with("./") && cmd.command.len() > 2 {
-                        let mut buf = vec![0; core.fs.get_real_cwd().len() + 1];
-                        path = PathBuf::from(CStr16::from_str_with_buf(&core.fs.get_real_cwd(), &mut buf).unwrap());
-
-                        let mut buf = vec![0; cmd.command.len() + 1];
-                        path.push(PathBuf::from(CStr16::from_str_with_buf(&cmd.command, &mut buf).unwrap()));
-                    } else {
-                        let mut buf = vec![0; cmd.command.len() + 1];
-                        path.push(PathBuf::from(CStr16::from_str_with_buf(&cmd.command, &mut buf).unwrap()));
-                    }
+                    core.set_shared_variable("cwd", core.fs.get_real_cwd().as_bytes()).unwrap();
+                    let path = command_path(&core, &cmd.command);
 
                     match core.execute_user_binary(&path.to_string()) {
                         Ok(_) | Err(ExecBinaryError::Finished) => (),
@@ -158,10 +322,13 @@ fn main(_image: Handle, mut system_table: SystemTable<Boot>) -> Status {
                         },
                         Err(ExecBinaryError::OutOfMemory) => println!("The system is low on memory and \"{}\" had to be stopped.", cmd.command),
                         Err(ExecBinaryError::Runtime(e)) => println!("The program \"{}\" has stopped working. ({})", cmd.command, status_to_text(e.status())),
-                        Err(ExecBinaryError::Unsupported) => println!("\"{}\" is not a valid BunnyOS program.", cmd.command)
+                        Err(ExecBinaryError::Unsupported) => println!("\"{}\" is not a valid BunnyOS program.", cmd.command),
+                        Err(ExecBinaryError::Unsigned) => println!("\"{}\" is not signed and cannot be started under the current security policy.", cmd.command),
+                        Err(ExecBinaryError::BadSignature) => println!("The digital signature of \"{}\" could not be verified. The program may have been tampered with.", cmd.command)
                     }
 
                     core.delete_shared_variable("argv").unwrap();
+                    core.delete_shared_variable("cwd").unwrap();
                 }
             }
         }
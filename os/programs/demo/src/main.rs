@@ -1,5 +1,4 @@
-#![no_std]
-#![no_main]
+#![cfg_attr(target_os = "uefi", no_std, no_main)]
 
 use rstd::dbg;
 use rstd::prelude::*;
@@ -1,22 +1,67 @@
 #![no_main]
 #![no_std]
 
+mod menu;
+mod recovery;
+
+use alloc::format;
 use alloc::string::{String, ToString};
 use uefi::prelude::*;
-use uefi::{print, println};
-use russet_common::{CoreServices, DEFAULT_KERNEL};
+use uefi::allocator::Allocator;
+use russet_common::println;
+use russet_common::{status_to_text, CoreServices, ExecBinaryError, COMMAND_LINE_VARIABLE};
+use russet_common::chainload::is_efi_path;
+use russet_common::network::{format_address, NetworkPath, NETWORK_VARIABLE};
+use russet_common::boot::{BootConfig, BootConfigErrorKind, BOOT_CONFIG, LAST_ENTRY_VARIABLE};
+use crate::menu::Choice;
+use crate::recovery::Action;
 
 extern crate alloc;
 
+#[global_allocator]
+static ALLOCATOR: Allocator = Allocator;
+
+fn load_config(core: &CoreServices) -> BootConfig {
+    let Some(text) = core.fs.read_file(BOOT_CONFIG) else {
+        return BootConfig::default();
+    };
+
+    match BootConfig::parse(&text) {
+        Ok(config) => config,
+        Err(e) => {
+            let reason = match e.kind {
+                BootConfigErrorKind::Syntax => String::from("syntax error"),
+                BootConfigErrorKind::UnknownKey(key) => format!("unknown setting \"{key}\""),
+                BootConfigErrorKind::InvalidValue(key) => format!("invalid value for \"{key}\""),
+                BootConfigErrorKind::DuplicateEntry(id) => format!("entry \"{id}\" is defined twice"),
+                BootConfigErrorKind::MissingKernel(id) => format!("entry \"{id}\" has no kernel or EFI application"),
+                BootConfigErrorKind::UnknownDefault(id) => format!("default entry \"{id}\" does not exist"),
+                BootConfigErrorKind::NoEntries => String::from("no entries are defined")
+            };
+            println!("The boot configuration is invalid (line {}: {reason}), using the default kernel.", e.line);
+            BootConfig::default()
+        }
+    }
+}
+
+fn command_line(kernel: &str, arguments: &str) -> String {
+    let kernel = if kernel.contains(' ') { format!("\"{kernel}\"") } else { String::from(kernel) };
+    match arguments.trim() {
+        "" => kernel,
+        arguments => format!("{kernel} {arguments}")
+    }
+}
+
 #[entry]
 #[allow(unused_must_use)]
 fn main(_image: Handle, mut system_table: SystemTable<Boot>) -> Status {
     uefi::helpers::init(&mut system_table).unwrap();
     let mut core;
+    let mut st;
 
     unsafe {
         core = CoreServices::init(system_table, true);
-        let mut st = core.get_system_table();
+        st = core.get_system_table();
 
         core.transfer_system_table(_image, build_info::format!(
             "Version: {} {}\nCompiler: {}\nRevision: {}",
@@ -36,21 +81,120 @@ fn main(_image: Handle, mut system_table: SystemTable<Boot>) -> Status {
     core.set_shared_variable("Russet.Bootloader",
         build_info::format!("{}", $.crate_info.version).as_bytes())
         .unwrap();
+    core.begin_timeline("rouse started");
+
+    let title = build_info::format!("rouse bootloader {}", $.crate_info.version);
+    let config = load_config(&core);
+    core.begin_serial(config.serial);
+    let mut last = core.get_shared_variable(LAST_ENTRY_VARIABLE).ok()
+        .and_then(|(data, _)| String::from_utf8(data).ok());
+    let mut selected = config.default_entry(last.as_deref());
+    let mut timeout = Some(config.timeout);
 
-    let mut path = String::from(DEFAULT_KERNEL);
+    let mut next = None;
+    let mut failed = None;
+    let mut retried = false;
 
     loop {
-        println!("{} ({path})", &build_info::format!("rouse bootloader {}", $.crate_info.version));
-
-        if core.execute_kmode_binary(&path, false).is_err() {
-            println!("\nThe kernel \"{path}\" could not be loaded at this time.");
-            loop {
-                print!("Rouse> ");
-                path = core.readline();
-                if path.trim() != "" {
-                    break;
+        let choice = match next.take() {
+            Some(choice) => choice,
+            None => menu::choose(&mut st, title, &config, selected, timeout.take())
+        };
+        core.mark("boot entry selected");
+
+        let (slotted, efi, path, arguments) = match choice {
+            Choice::Entry(i) | Choice::Edit(i) => {
+                selected = i;
+                let entry = &config.entries[i];
+                if config.remember && last.as_deref() != Some(entry.id.as_str()) {
+                    core.set_persistent_variable(LAST_ENTRY_VARIABLE, entry.id.as_bytes());
+                    last = Some(entry.id.clone());
+                }
+
+                let arguments = match choice {
+                    Choice::Edit(_) => {
+                        println!("Arguments for {}:", entry.name);
+                        core.edit_line(&entry.arguments)
+                    },
+                    _ => entry.arguments.clone()
+                };
+
+                if entry.slots && !entry.efi {
+                    let boot = core.begin_slot_boot(config.attempts);
+                    if let Some(failed) = boot.failed {
+                        println!("Slot {} failed to boot {} time(s), falling back to slot {}.",
+                            failed.name(), config.attempts, boot.slot.name());
+                    }
+                    let arguments = format!("{} --slot={}", arguments.trim(), boot.slot.name());
+                    (Some(i), false, boot.slot.path(&entry.kernel), arguments)
+                } else {
+                    (None, entry.efi, entry.kernel.clone(), arguments)
+                }
+            },
+            Choice::Prompt => match recovery::console(&mut core, failed.take()) {
+                Action::Boot(path, arguments) => (None, is_efi_path(&path), path, arguments),
+                Action::Menu => continue
+            }
+        };
+
+        if efi {
+            println!("{title} ({path})");
+            match core.chainload(&path, &arguments) {
+                Err(ExecBinaryError::Finished) => continue,
+                Err(ExecBinaryError::NotFound) => println!("\nThe application \"{path}\" could not be found."),
+                Err(ExecBinaryError::Unsupported) => println!("\nThe file \"{path}\" is not a valid EFI application."),
+                Err(ExecBinaryError::Load(e) | ExecBinaryError::Runtime(e)) => {
+                    println!("\nThe application \"{path}\" failed: {}", status_to_text(e.status()));
+                },
+                _ => println!("\nThe application \"{path}\" could not be started.")
+            }
+            println!("Press any key to return to the boot menu.");
+            core.wait_for_key();
+            continue;
+        }
+
+        let kernel = match NetworkPath::parse(&path) {
+            Some(network) => match core.start_network(network.server) {
+                Ok(server) => {
+                    println!("Loading {} from {}", network.path, format_address(server));
+                    network.path
+                },
+                Err(e) => {
+                    println!("\nThe kernel \"{path}\" could not be loaded over the network: {e}.");
+                    failed = Some((path, arguments));
+                    next = Some(Choice::Prompt);
+                    continue;
                 }
+            },
+            None => {
+                core.delete_shared_variable(NETWORK_VARIABLE);
+                path.clone()
             }
+        };
+
+        let line = command_line(&path, &arguments);
+        core.set_shared_variable(COMMAND_LINE_VARIABLE, line.as_bytes());
+        if !(config.splash && core.begin_splash()) {
+            println!("{title} ({path})");
+        }
+
+        if let Err(e) = core.execute_kmode_binary(&kernel, false) {
+            core.end_splash();
+            match e {
+                ExecBinaryError::Unsigned => println!("\nThe kernel \"{path}\" is not signed by a trusted key."),
+                ExecBinaryError::BadSignature => println!("\nThe kernel \"{path}\" has an invalid signature."),
+                _ => println!("\nThe kernel \"{path}\" could not be loaded at this time.")
+            }
+
+            if let (Some(i), false) = (slotted, retried) {
+                core.fail_slot_boot(config.attempts);
+                next = Some(Choice::Entry(i));
+                retried = true;
+                continue;
+            }
+
+            failed = Some((path, arguments));
+            next = Some(Choice::Prompt);
         } else {
             panic!("KMODE_EXCEPTION_NOT_HANDLED");
         }
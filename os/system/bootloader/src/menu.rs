@@ -0,0 +1,132 @@
+use alloc::vec;
+use uefi::prelude::*;
+use russet_common::println;
+use uefi::Event;
+use uefi::proto::console::text::{Color, Key, ScanCode};
+use uefi::table::boot::{EventType, TimerTrigger, Tpl};
+use russet_common::boot::BootConfig;
+use russet_common::serial::{self, SerialMode};
+
+const SECOND: u64 = 10_000_000;
+const SERIAL_POLL: u64 = 100_000;
+
+pub enum Choice {
+    Entry(usize),
+    Edit(usize),
+    Prompt
+}
+
+enum Input {
+    Tick,
+    Key(Option<Key>)
+}
+
+#[allow(unused_must_use)]
+fn draw(st: &mut SystemTable<Boot>, title: &str, config: &BootConfig, selected: usize, remaining: Option<u32>) {
+    let stdout = st.stdout();
+    stdout.set_color(Color::LightGray, Color::Black);
+    stdout.clear();
+    serial::clear();
+
+    println!("{title}\n");
+
+    for (i, entry) in config.entries.iter().enumerate() {
+        if i == selected {
+            st.stdout().set_color(Color::Black, Color::LightGray);
+            serial::highlight(true);
+        }
+        println!("  {:<40}", entry.name);
+        st.stdout().set_color(Color::LightGray, Color::Black);
+        serial::highlight(false);
+    }
+
+    println!("\nUse the arrow keys to select an entry and press Enter to boot it.");
+    println!("Press E to edit the arguments of the entry or C to open the recovery console.");
+
+    if let Some(remaining) = remaining {
+        println!("\nThe highlighted entry will be started automatically in {remaining} second(s).");
+    }
+}
+
+fn create_timer(st: &SystemTable<Boot>, interval: u64) -> Option<Event> {
+    let timer = unsafe { st.boot_services().create_event(EventType::TIMER, Tpl::APPLICATION, None, None) }.ok()?;
+    match st.boot_services().set_timer(&timer, TimerTrigger::Periodic(interval)) {
+        Ok(()) => Some(timer),
+        Err(_) => {
+            let _ = st.boot_services().close_event(timer);
+            None
+        }
+    }
+}
+
+fn wait_input(st: &mut SystemTable<Boot>, timer: Option<&Event>, poll: Option<&Event>) -> Input {
+    loop {
+        if let Some(key) = serial::read_key() {
+            return Input::Key(Some(key));
+        }
+
+        let mut events = vec![st.stdin().wait_for_key_event().unwrap()];
+        events.extend(timer.map(|timer| unsafe { timer.unsafe_clone() }));
+        events.extend(poll.map(|poll| unsafe { poll.unsafe_clone() }));
+
+        match st.boot_services().wait_for_event(&mut events) {
+            Ok(1) if timer.is_some() => return Input::Tick,
+            Ok(i) if i > 0 => continue,
+            _ => return Input::Key(st.stdin().read_key().ok().flatten())
+        }
+    }
+}
+
+pub fn choose(st: &mut SystemTable<Boot>, title: &str, config: &BootConfig, mut selected: usize, timeout: Option<u32>) -> Choice {
+    if timeout == Some(0) {
+        return Choice::Entry(selected);
+    }
+
+    let mut remaining = timeout;
+    let timer = remaining.and_then(|_| create_timer(st, SECOND));
+    let poll = (serial::mode() == SerialMode::Full).then(|| create_timer(st, SERIAL_POLL)).flatten();
+
+    if timer.is_none() {
+        remaining = None;
+    }
+
+    let choice = loop {
+        draw(st, title, config, selected, remaining);
+
+        let key = match wait_input(st, timer.as_ref().filter(|_| remaining.is_some()), poll.as_ref()) {
+            Input::Tick => {
+                remaining = remaining.map(|remaining| remaining.saturating_sub(1));
+                if remaining == Some(0) {
+                    break Choice::Entry(selected);
+                }
+                continue;
+            },
+            Input::Key(key) => key
+        };
+
+        if remaining.take().is_some() {
+            if let Some(timer) = &timer {
+                let _ = st.boot_services().set_timer(timer, TimerTrigger::Cancel);
+            }
+        }
+
+        let count = config.entries.len();
+        match key {
+            Some(Key::Special(ScanCode::UP)) => selected = (selected + count - 1) % count,
+            Some(Key::Special(ScanCode::DOWN)) => selected = (selected + 1) % count,
+            Some(Key::Printable(key)) => match char::from(key) {
+                '\r' => break Choice::Entry(selected),
+                'e' | 'E' => break Choice::Edit(selected),
+                'c' | 'C' => break Choice::Prompt,
+                _ => ()
+            },
+            _ => ()
+        }
+    };
+
+    for timer in [timer, poll].into_iter().flatten() {
+        let _ = st.boot_services().close_event(timer);
+    }
+
+    choice
+}
@@ -0,0 +1,181 @@
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use russet_common::println;
+use russet_common::{Codec, Completion, CoreServices, ElfContext, ElfError, DEFAULT_KERNEL, SUPPORTED_ABI};
+use russet_common::parser::Command;
+
+const COMMANDS: [&str; 8] = ["args", "boot", "help", "info", "ls", "menu", "retry", "volumes"];
+
+pub enum Action {
+    Boot(String, String),
+    Menu
+}
+
+fn real_path(path: &str) -> String {
+    let path = path.trim_end_matches('/');
+    format!("\\rootfs{}{}", if path.starts_with('/') { "" } else { "\\" }, path.replace('/', "\\"))
+}
+
+fn split_line(line: &str) -> (&str, &str) {
+    match line.chars().next() {
+        Some(quote @ ('"' | '\'')) => match line[1..].split_once(quote) {
+            Some((_, rest)) => (&line[..line.len() - rest.len()], rest.trim()),
+            None => (line, "")
+        },
+        _ => line.split_once(' ').map(|(first, rest)| (first, rest.trim())).unwrap_or((line, ""))
+    }
+}
+
+fn common_prefix<'a>(names: &[&'a str]) -> &'a str {
+    let first = names.first().copied().unwrap_or("");
+    let end = names.iter().skip(1).fold(first.len(), |end, name| {
+        first.char_indices()
+            .zip(name.chars())
+            .take_while(|((_, a), b)| a.eq_ignore_ascii_case(b))
+            .map(|((i, a), _)| i + a.len_utf8())
+            .last()
+            .unwrap_or(0)
+            .min(end)
+    });
+    &first[..end]
+}
+
+fn complete(core: &CoreServices, line: &str) -> Option<Completion> {
+    let start = line.rfind(' ').map(|i| i + 1).unwrap_or(0);
+    let word = &line[start..];
+
+    let (dir, prefix, entries): (&str, &str, Vec<(String, bool)>) = match word.rfind('/') {
+        Some(i) => (&word[..=i], &word[i + 1..], core.fs.list_dir(&real_path(&word[..=i]))?),
+        None if start == 0 => ("", word, COMMANDS.iter().map(|command| (command.to_string(), false)).collect()),
+        None => return None
+    };
+
+    let matches: Vec<&(String, bool)> = entries.iter()
+        .filter(|(name, _)| name.get(..prefix.len()).is_some_and(|start| start.eq_ignore_ascii_case(prefix)))
+        .collect();
+
+    let completed = match matches.as_slice() {
+        [] => return None,
+        [(name, true)] => format!("{dir}{name}/"),
+        [(name, false)] => format!("{dir}{name} "),
+        matches => format!("{dir}{}", common_prefix(&matches.iter().map(|(name, _)| name.as_str()).collect::<Vec<_>>()))
+    };
+
+    Some(Completion {
+        line: format!("{}{completed}", &line[..start]),
+        candidates: matches.iter().map(|(name, dir)| if *dir { format!("{name}/") } else { name.clone() }).collect()
+    })
+}
+
+fn list(core: &CoreServices, path: &str) {
+    match core.fs.list_dir(&real_path(path)) {
+        Some(entries) if entries.is_empty() => println!("The directory \"{path}\" is empty."),
+        Some(entries) => for (name, dir) in entries {
+            println!("  {name}{}", if dir { "/" } else { "" });
+        },
+        None => println!("The directory \"{path}\" could not be found.")
+    }
+}
+
+fn error_text(e: &ElfError) -> &'static str {
+    match e {
+        ElfError::Corrupted => "corrupted",
+        ElfError::BadSignature => "not trusted",
+        _ => "invalid"
+    }
+}
+
+fn info(core: &CoreServices, path: &str) {
+    let bundle = if path.starts_with('/') { String::from(path) } else { format!("/{path}") };
+    let Some(data) = core.read_kernel_bundle(&bundle) else {
+        println!("The file \"{path}\" could not be read.");
+        return;
+    };
+
+    let report = match core.inspect_bundle(&data) {
+        Ok(report) => report,
+        Err(e) => {
+            println!("The file \"{path}\" is not a valid bundle ({e:?}).");
+            return;
+        }
+    };
+
+    let header = &report.header;
+    println!("  Context:   {}", match header.context {
+        ElfContext::Kernel => "kernel",
+        ElfContext::User => "user"
+    });
+    println!("  ABI:       {}{}", header.abi_version, if SUPPORTED_ABI.contains(&header.abi_version) { "" } else { " (unsupported)" });
+    println!("  Payload:   {} bytes{}", header.size, match header.codec {
+        Codec::None => "",
+        Codec::Lz4 => ", LZ4 compressed"
+    });
+    println!("  Checksum:  {:08x} ({})", header.checksum, match &report.checksum {
+        Ok(()) => "valid",
+        Err(e) => error_text(e)
+    });
+    println!("  Signature: {}", match &report.signature {
+        None => "unsigned",
+        Some(Ok(())) => "trusted",
+        Some(Err(e)) => error_text(e)
+    });
+
+    if let Some(metadata) = report.metadata {
+        println!("  Program:   {} {}", metadata.name, metadata.version);
+    }
+}
+
+fn help() {
+    println!("  ls [directory]           List a directory on the system volume");
+    println!("  info <path>              Show the bundle header of a kernel or program");
+    println!("  args [arguments]         Set or edit the kernel arguments");
+    println!("  boot [path [arguments]]  Boot a kernel or EFI application");
+    println!("  retry                    Boot the last kernel again with the current arguments");
+    println!("  volumes                  Show the number of volumes available to EFI paths");
+    println!("  menu                     Return to the boot menu");
+    println!("Any other input is booted as a path followed by its arguments. Press Tab to complete paths.");
+}
+
+pub fn console(core: &mut CoreServices, failed: Option<(String, String)>) -> Action {
+    let (kernel, mut arguments) = failed.clone().unwrap_or((String::from(DEFAULT_KERNEL), String::new()));
+
+    println!("\nrouse recovery console, type help for a list of commands.");
+    if failed.is_some() {
+        println!("Use retry to boot \"{kernel}\" again or args to change its arguments first.");
+    }
+
+    loop {
+        let line = core.edit_line_with("Rouse> ", "", complete);
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+
+        if Command::build(line).is_err() {
+            println!("The command line contains mismatched quotes.");
+            continue;
+        }
+
+        let (command, rest) = split_line(line);
+        match command {
+            "help" => help(),
+            "menu" => return Action::Menu,
+            "ls" => list(core, if rest.is_empty() { "/" } else { rest }),
+            "info" if rest.is_empty() => println!("Usage: info <path>"),
+            "info" => info(core, rest),
+            "volumes" => println!("{} volume(s) are available as fs0: and up.", core.volumes().len()),
+            "args" if rest.is_empty() => {
+                arguments = core.edit_line_with("Arguments: ", &arguments, |_, _| None).trim().to_string();
+            },
+            "args" => arguments = rest.to_string(),
+            "retry" | "boot" if rest.is_empty() => return Action::Boot(kernel, arguments),
+            "boot" => {
+                let (path, rest) = split_line(rest);
+                let arguments = if rest.is_empty() { arguments } else { rest.to_string() };
+                return Action::Boot(path.trim_matches(['"', '\'']).to_string(), arguments);
+            },
+            path => return Action::Boot(path.trim_matches(['"', '\'']).to_string(), rest.to_string())
+        }
+    }
+}
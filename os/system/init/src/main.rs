@@ -2,13 +2,19 @@
 #![no_std]
 
 use alloc::format;
-use alloc::string::{String, ToString};
+use alloc::string::ToString;
 use uefi::prelude::*;
-use uefi::{print, println};
-use russet_common::{CoreServices, DEFAULT_SHELL};
+use uefi::allocator::Allocator;
+use russet_common::{print, println};
+use russet_common::CoreServices;
+use russet_common::boot::KernelOptions;
+use russet_common::splash::Stage;
 
 extern crate alloc;
 
+#[global_allocator]
+static ALLOCATOR: Allocator = Allocator;
+
 #[entry]
 fn main(_image: Handle, mut system_table: SystemTable<Boot>) -> Status {
     uefi::helpers::init(&mut system_table).unwrap();
@@ -29,11 +35,25 @@ fn main(_image: Handle, mut system_table: SystemTable<Boot>) -> Status {
     core.set_shared_variable("Russet.Init",
         build_info::format!("{}", $.crate_info.version).as_bytes())
         .unwrap();
+    core.mark("sable started");
+
+    let options = core.command_line().map(|command| KernelOptions::from(&command)).unwrap_or_default();
+    let mut path = options.shell;
+
+    let splash = core.splash_progress(Stage::Init);
+    log::info!("shell {path}");
 
-    let mut path = String::from(DEFAULT_SHELL);
+    if options.journal && !core.save_journal() {
+        log::warn!("journal could not be saved to {}", russet_common::journal::JOURNAL_FILE);
+    }
 
     loop {
-        println!();
+        if splash {
+            core.splash_progress(Stage::Shell);
+            core.end_splash();
+        } else {
+            println!();
+        }
 
         let string = format!("\\rootfs{}", path.replace("/", "\\"));
         if core.execute_user_binary(&string).is_err() {
@@ -0,0 +1,246 @@
+use core::arch::{asm, global_asm};
+use core::fmt::{Display, Formatter};
+use core::mem::size_of;
+use core::ptr::addr_of;
+
+const GDT_ENTRIES: usize = 32;
+const STUB_SIZE: usize = 16;
+const EXCEPTIONS: usize = 32;
+const INTERRUPT_GATE: u8 = 0x8e;
+const INTERRUPT_FLAG: u64 = 1 << 9;
+const DOUBLE_FAULT: usize = 8;
+const DOUBLE_FAULT_IST: u8 = 1;
+const DOUBLE_FAULT_STACK_SIZE: usize = 16 * 1024;
+
+const EXCEPTION_NAMES: [&str; EXCEPTIONS] = [
+    "DIVIDE_ERROR", "DEBUG", "NON_MASKABLE_INTERRUPT", "BREAKPOINT",
+    "OVERFLOW", "BOUND_RANGE_EXCEEDED", "INVALID_OPCODE", "DEVICE_NOT_AVAILABLE",
+    "DOUBLE_FAULT", "COPROCESSOR_SEGMENT_OVERRUN", "INVALID_TSS", "SEGMENT_NOT_PRESENT",
+    "STACK_SEGMENT_FAULT", "GENERAL_PROTECTION_FAULT", "PAGE_FAULT", "RESERVED_EXCEPTION",
+    "X87_FLOATING_POINT", "ALIGNMENT_CHECK", "MACHINE_CHECK", "SIMD_FLOATING_POINT",
+    "VIRTUALIZATION_EXCEPTION", "CONTROL_PROTECTION", "RESERVED_EXCEPTION", "RESERVED_EXCEPTION",
+    "RESERVED_EXCEPTION", "RESERVED_EXCEPTION", "RESERVED_EXCEPTION", "RESERVED_EXCEPTION",
+    "HYPERVISOR_INJECTION", "VMM_COMMUNICATION", "SECURITY_EXCEPTION", "RESERVED_EXCEPTION"
+];
+
+#[repr(C)]
+struct ExceptionFrame {
+    rax: u64,
+    rbx: u64,
+    rcx: u64,
+    rdx: u64,
+    rsi: u64,
+    rdi: u64,
+    rbp: u64,
+    r8: u64,
+    r9: u64,
+    r10: u64,
+    r11: u64,
+    r12: u64,
+    r13: u64,
+    r14: u64,
+    r15: u64,
+    vector: u64,
+    error_code: u64,
+    rip: u64,
+    cs: u64,
+    rflags: u64,
+    rsp: u64,
+    ss: u64
+}
+
+#[repr(C, packed)]
+struct TaskStateSegment {
+    reserved0: u32,
+    privilege_stacks: [u64; 3],
+    reserved1: u64,
+    interrupt_stacks: [u64; 7],
+    reserved2: u64,
+    reserved3: u16,
+    io_map_base: u16
+}
+
+#[repr(C)]
+#[derive(Clone, Copy)]
+struct IdtEntry {
+    offset_low: u16,
+    selector: u16,
+    ist: u8,
+    attributes: u8,
+    offset_mid: u16,
+    offset_high: u32,
+    reserved: u32
+}
+
+#[repr(C, packed)]
+struct DescriptorTablePointer {
+    limit: u16,
+    base: u64
+}
+
+#[repr(C, align(16))]
+struct Stack([u8; DOUBLE_FAULT_STACK_SIZE]);
+
+static mut GDT: [u64; GDT_ENTRIES] = [0; GDT_ENTRIES];
+static mut IDT: [IdtEntry; 256] = [IdtEntry::missing(); 256];
+static mut DOUBLE_FAULT_STACK: Stack = Stack([0; DOUBLE_FAULT_STACK_SIZE]);
+static mut TSS: TaskStateSegment = TaskStateSegment {
+    reserved0: 0,
+    privilege_stacks: [0; 3],
+    reserved1: 0,
+    interrupt_stacks: [0; 7],
+    reserved2: 0,
+    reserved3: 0,
+    io_map_base: size_of::<TaskStateSegment>() as u16
+};
+
+global_asm!(
+    ".macro exception_stub vector, error_code",
+    ".balign 16",
+    ".if \\error_code == 0",
+    "push 0",
+    ".endif",
+    "push \\vector",
+    "jmp velm_exception_common",
+    ".endm",
+    ".balign 16",
+    "velm_exception_stubs:",
+    "exception_stub 0, 0", "exception_stub 1, 0", "exception_stub 2, 0", "exception_stub 3, 0",
+    "exception_stub 4, 0", "exception_stub 5, 0", "exception_stub 6, 0", "exception_stub 7, 0",
+    "exception_stub 8, 1", "exception_stub 9, 0", "exception_stub 10, 1", "exception_stub 11, 1",
+    "exception_stub 12, 1", "exception_stub 13, 1", "exception_stub 14, 1", "exception_stub 15, 0",
+    "exception_stub 16, 0", "exception_stub 17, 1", "exception_stub 18, 0", "exception_stub 19, 0",
+    "exception_stub 20, 0", "exception_stub 21, 1", "exception_stub 22, 0", "exception_stub 23, 0",
+    "exception_stub 24, 0", "exception_stub 25, 0", "exception_stub 26, 0", "exception_stub 27, 0",
+    "exception_stub 28, 0", "exception_stub 29, 1", "exception_stub 30, 1", "exception_stub 31, 0",
+    "velm_exception_common:",
+    "push r15", "push r14", "push r13", "push r12", "push r11", "push r10", "push r9", "push r8",
+    "push rbp", "push rdi", "push rsi", "push rdx", "push rcx", "push rbx", "push rax",
+    "mov rdi, rsp",
+    "and rsp, -16",
+    "call {handler}",
+    "ud2",
+    handler = sym exception_handler
+);
+
+extern "C" {
+    fn velm_exception_stubs();
+}
+
+impl IdtEntry {
+    const fn missing() -> Self {
+        Self { offset_low: 0, selector: 0, ist: 0, attributes: 0, offset_mid: 0, offset_high: 0, reserved: 0 }
+    }
+
+    fn new(handler: u64, selector: u16, ist: u8) -> Self {
+        Self {
+            offset_low: handler as u16,
+            selector,
+            ist,
+            attributes: INTERRUPT_GATE,
+            offset_mid: (handler >> 16) as u16,
+            offset_high: (handler >> 32) as u32,
+            reserved: 0
+        }
+    }
+}
+
+fn control_registers() -> [u64; 4] {
+    let (cr0, cr2, cr3, cr4): (u64, u64, u64, u64);
+    unsafe {
+        asm!("mov {}, cr0", out(reg) cr0, options(nomem, nostack));
+        asm!("mov {}, cr2", out(reg) cr2, options(nomem, nostack));
+        asm!("mov {}, cr3", out(reg) cr3, options(nomem, nostack));
+        asm!("mov {}, cr4", out(reg) cr4, options(nomem, nostack));
+    }
+    [cr0, cr2, cr3, cr4]
+}
+
+struct Registers<'a>(&'a ExceptionFrame, [u64; 4]);
+
+impl Display for Registers<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        let (frame, [cr0, cr2, cr3, cr4]) = (self.0, self.1);
+        let name = EXCEPTION_NAMES[frame.vector as usize % EXCEPTIONS];
+
+        writeln!(f, "{name}\n")?;
+        writeln!(f, "RAX={:016x} RBX={:016x} RCX={:016x}", frame.rax, frame.rbx, frame.rcx)?;
+        writeln!(f, "RDX={:016x} RSI={:016x} RDI={:016x}", frame.rdx, frame.rsi, frame.rdi)?;
+        writeln!(f, "RBP={:016x} RSP={:016x} R8 ={:016x}", frame.rbp, frame.rsp, frame.r8)?;
+        writeln!(f, "R9 ={:016x} R10={:016x} R11={:016x}", frame.r9, frame.r10, frame.r11)?;
+        writeln!(f, "R12={:016x} R13={:016x} R14={:016x}", frame.r12, frame.r13, frame.r14)?;
+        writeln!(f, "R15={:016x} RIP={:016x} RFL={:016x}", frame.r15, frame.rip, frame.rflags)?;
+        writeln!(f, "CS ={:04x} SS ={:04x} ERR={:016x}", frame.cs, frame.ss, frame.error_code)?;
+        write!(f, "CR0={cr0:016x} CR2={cr2:016x} CR3={cr3:016x} CR4={cr4:016x}")
+    }
+}
+
+struct StopCode<'a>(&'a ExceptionFrame, u64);
+
+impl Display for StopCode<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        write!(f, "{:#010X} ({:#018X}, {:#018X}, {:#018X})", self.0.vector, self.0.error_code, self.1, self.0.rip)
+    }
+}
+
+extern "sysv64" fn exception_handler(frame: &ExceptionFrame) -> ! {
+    let registers = control_registers();
+    unsafe {
+        russet_common::stop(&StopCode(frame, registers[1]), &Registers(frame, registers), Some((frame.rip as usize, frame.rbp as usize)))
+    }
+}
+
+#[allow(static_mut_refs)]
+pub unsafe fn init() -> Result<(), usize> {
+    let mut gdtr = DescriptorTablePointer { limit: 0, base: 0 };
+    let mut idtr = DescriptorTablePointer { limit: 0, base: 0 };
+    asm!("sgdt [{}]", in(reg) &mut gdtr, options(nostack));
+    asm!("sidt [{}]", in(reg) &mut idtr, options(nostack));
+
+    let code: u16;
+    asm!("mov {:x}, cs", out(reg) code, options(nomem, nostack));
+
+    let firmware = (gdtr.limit as usize + 1) / size_of::<u64>();
+    if firmware + 2 > GDT_ENTRIES {
+        return Err(firmware);
+    }
+
+    let stack = addr_of!(DOUBLE_FAULT_STACK) as u64 + DOUBLE_FAULT_STACK_SIZE as u64;
+    let mut stacks = [0; 7];
+    stacks[DOUBLE_FAULT_IST as usize - 1] = stack;
+    TSS.interrupt_stacks = stacks;
+
+    let tss = addr_of!(TSS) as u64;
+    let limit = size_of::<TaskStateSegment>() as u64 - 1;
+    GDT[..firmware].copy_from_slice(core::slice::from_raw_parts(gdtr.base as *const u64, firmware));
+    GDT[firmware] = (limit & 0xffff)
+        | ((tss & 0xff_ffff) << 16)
+        | (0x89 << 40)
+        | (((limit >> 16) & 0xf) << 48)
+        | (((tss >> 24) & 0xff) << 56);
+    GDT[firmware + 1] = tss >> 32;
+
+    let vectors = ((idtr.limit as usize + 1) / size_of::<IdtEntry>()).min(IDT.len());
+    IDT[..vectors].copy_from_slice(core::slice::from_raw_parts(idtr.base as *const IdtEntry, vectors));
+
+    for (vector, entry) in IDT.iter_mut().take(EXCEPTIONS).enumerate() {
+        let handler = velm_exception_stubs as *const () as usize + vector * STUB_SIZE;
+        let ist = if vector == DOUBLE_FAULT { DOUBLE_FAULT_IST } else { 0 };
+        *entry = IdtEntry::new(handler as u64, code, ist);
+    }
+
+    let gdt = DescriptorTablePointer { limit: ((firmware + 2) * size_of::<u64>() - 1) as u16, base: addr_of!(GDT) as u64 };
+    let idt = DescriptorTablePointer { limit: (size_of::<[IdtEntry; 256]>() - 1) as u16, base: addr_of!(IDT) as u64 };
+
+    let flags: u64;
+    asm!("pushfq", "pop {}", "cli", out(reg) flags);
+    asm!("lgdt [{}]", in(reg) &gdt, options(readonly, nostack));
+    asm!("ltr {:x}", in(reg) (firmware * size_of::<u64>()) as u16, options(nomem, nostack));
+    asm!("lidt [{}]", in(reg) &idt, options(readonly, nostack));
+
+    if flags & INTERRUPT_FLAG != 0 {
+        asm!("sti");
+    }
+
+    Ok(())
+}
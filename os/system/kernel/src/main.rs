@@ -3,17 +3,28 @@
 
 use alloc::format;
 use uefi::prelude::*;
-use uefi::{print, println};
-use russet_common::{CoreServices, OS_VERSION};
-use alloc::string::ToString;
+use russet_common::{print, println};
+use russet_common::{CoreServices, SignaturePolicy, BUILD_SIGNATURE_POLICY, COMMAND_LINE_VARIABLE, DEFAULT_KERNEL, OS_VERSION};
+use russet_common::boot::KernelOptions;
+use russet_common::memory::SystemAllocator;
+use russet_common::splash::Stage;
+use russet_common::sysinfo::cpu_information;
+use alloc::string::{String, ToString};
 
 extern crate alloc;
 
+#[global_allocator]
+static ALLOCATOR: SystemAllocator = SystemAllocator;
+
+mod interrupts;
+mod native;
+
 #[entry]
 #[allow(unused_must_use)]
 fn main(_image: Handle, mut system_table: SystemTable<Boot>) -> Status {
     uefi::helpers::init(&mut system_table).unwrap();
     let mut core;
+    let exceptions;
 
     unsafe {
         core = CoreServices::init(system_table, true);
@@ -21,6 +32,7 @@ fn main(_image: Handle, mut system_table: SystemTable<Boot>) -> Status {
             "Version: {} {}\nCompiler: {}\nRevision: {}",
             $.crate_info.name, $.crate_info.version, $.compiler, $.timestamp
         ).to_string());
+        exceptions = interrupts::init();
     }
 
     if core.get_shared_variable("Russet.Version").is_ok() {
@@ -30,12 +42,80 @@ fn main(_image: Handle, mut system_table: SystemTable<Boot>) -> Status {
     core.set_shared_variable("Russet.Version",
         build_info::format!("{}", $.crate_info.version).as_bytes())
         .unwrap();
+    core.mark("velm started");
+    core.begin_journal();
 
     let os_string = format!("Russet {OS_VERSION} {}", &build_info::format!("{} {} {}-{}/{} rustc-{}", $.timestamp, $.target.cpu.arch, $.crate_info.name, $.crate_info.version, $.profile, $.compiler.version));
     core.set_shared_variable("Russet.OSString", os_string.as_bytes());
-    println!("{os_string}");
-    print!("Running on {} {} (HAL {})", core.firmware_vendor(), core.firmware_revision(), core.uefi_revision());
+    log::info!("{os_string}");
+    log::info!("firmware {} {}, UEFI {}", core.firmware_vendor(), core.firmware_revision(), core.uefi_revision());
+    log::info!("processor {}", cpu_information().brand);
+    if let Err(entries) = exceptions {
+        log::warn!("the firmware GDT has {entries} entries, exception handlers were not installed");
+    }
+
+    let options = match core.command_line() {
+        Some(command) => KernelOptions::from(&command),
+        None => {
+            core.set_shared_variable(COMMAND_LINE_VARIABLE, DEFAULT_KERNEL.as_bytes());
+            KernelOptions::default()
+        }
+    };
+
+    if options.verbose {
+        core.end_splash();
+    }
+
+    let policy = match options.safe {
+        true => SignaturePolicy::Enforce,
+        false => BUILD_SIGNATURE_POLICY
+    };
+    core.set_signature_policy(policy);
+
+    if let Ok((command_line, _)) = core.get_shared_variable(COMMAND_LINE_VARIABLE) {
+        log::info!("command line: {}", String::from_utf8_lossy(&command_line));
+    }
+    log::info!("signature policy: {policy:?}");
+    if options.safe {
+        log::warn!("starting in safe mode");
+    }
+    if let Some(slot) = options.slot {
+        log::info!("slot {}", slot.name());
+    }
+
+    if !core.splash_progress(Stage::Kernel) {
+        println!("{os_string}");
+        print!("Running on {} {} (HAL {})", core.firmware_vendor(), core.firmware_revision(), core.uefi_revision());
+
+        if options.safe {
+            print!("\nStarting in safe mode");
+        }
+
+        if options.verbose {
+            if let Ok((command_line, _)) = core.get_shared_variable(COMMAND_LINE_VARIABLE) {
+                print!("\nCommand line: {}", String::from_utf8_lossy(&command_line));
+            }
+            print!("\nSignature policy: {policy:?}\nInit: {}", options.init);
+            if let Some(slot) = options.slot {
+                print!(" (slot {})", slot.name());
+            }
+        }
+    }
+
+    if options.native {
+        log::warn!("native mode is experimental, {} will not be started", options.init);
+        if !core.splash_active() {
+            print!("\nExiting boot services, further output goes to COM1");
+        }
+
+        core.mark("kernel memory manager");
+        log::info!("exiting boot services");
+        let info = core.boot_info();
+        let (system_table, map) = core.exit_boot_services();
+        native::run(system_table, map, info);
+    }
 
-    core.execute_kmode_binary("/System/Init", true);
+    log::info!("starting {}", options.init);
+    core.execute_kmode_binary(&options.init, true);
     panic!("CRITICAL_PROCESS_DIED");
 }
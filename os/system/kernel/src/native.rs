@@ -0,0 +1,67 @@
+use alloc::string::String;
+use core::arch::asm;
+use core::fmt::Write;
+use uefi::prelude::*;
+use uefi::table::Runtime;
+use uefi::table::boot::MemoryMap;
+use uefi::table::runtime::ResetType;
+use russet_common::boot::BootInfo;
+use russet_common::journal;
+use russet_common::memory;
+use russet_common::network::format_address;
+use russet_common::serial::{SerialPort, COM1};
+use russet_common::timeline::{timestamp, Mark};
+
+const MIB: u64 = 1024 * 1024;
+
+pub fn run(system_table: SystemTable<Runtime>, map: MemoryMap<'static>, mut info: BootInfo) -> ! {
+    let mut serial = SerialPort::new(COM1);
+    unsafe {
+        asm!("cli");
+        serial.init();
+    }
+
+    let Some(frames) = memory::init_kernel_memory(&map) else {
+        panic!("NO_USABLE_MEMORY");
+    };
+
+    if let Some(ref mut timeline) = info.timeline {
+        timeline.marks.push(Mark { label: String::from("boot services exited"), tsc: timestamp() });
+    }
+
+    let stats = memory::memory_stats(&frames);
+    log::info!("boot services exited, {} MiB of {} MiB free", stats.free / MIB, stats.total / MIB);
+    let _ = writeln!(serial, "{}", info.os_string);
+    let _ = writeln!(serial, "Running on {} {} (HAL {})", info.firmware_vendor, info.firmware_revision, info.uefi_revision);
+    let _ = writeln!(serial, "Boot services exited, {} MiB of {} MiB free", stats.free / MIB, stats.total / MIB);
+    let _ = writeln!(serial, "Kernel heap: {} KiB, {} bytes in use", stats.heap_size / 1024, stats.heap_used);
+    let _ = writeln!(serial, "Command line: {}", info.command_line);
+    let _ = writeln!(serial, "Bootloader: {}, kernel: {}", info.bootloader, info.kernel);
+    let _ = writeln!(serial, "Signature policy: {:?}", info.policy);
+
+    if let Some(slot) = info.options.slot {
+        let _ = writeln!(serial, "Slot: {}", slot.name());
+    }
+
+    if let Some(server) = info.network {
+        let _ = writeln!(serial, "Boot server: {}", format_address(server));
+    }
+
+    if let Some(ref timeline) = info.timeline {
+        let _ = writeln!(serial, "\nBoot timeline:");
+        for phase in timeline.phases() {
+            let _ = writeln!(serial, "  {:>8} us  {:>8} us  {}", phase.elapsed, phase.duration, phase.label);
+        }
+    }
+
+    let journal = journal::entries();
+    if !journal.is_empty() {
+        let _ = writeln!(serial, "\nKernel log:");
+        for entry in journal {
+            let _ = writeln!(serial, "  {entry}");
+        }
+    }
+
+    let _ = writeln!(serial, "\n{} cannot be started without boot services, shutting down.", info.options.init);
+    unsafe { system_table.runtime_services() }.reset(ResetType::SHUTDOWN, Status::SUCCESS, None)
+}
@@ -0,0 +1,17 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use relf::{Bundle, SymbolTable};
+
+fuzz_target!(|data: &[u8]| {
+    let Ok(bundle) = Bundle::parse(data) else {
+        return;
+    };
+
+    let _ = bundle.pe();
+
+    if let Some(symbols) = bundle.symbols.and_then(|data| SymbolTable::parse(data).ok()) {
+        let _ = symbols.lookup(0);
+        let _ = symbols.lookup(u32::MAX);
+    }
+});
@@ -0,0 +1,62 @@
+use std::fmt::{Display, Formatter};
+use std::io;
+
+#[derive(Debug)]
+pub enum Error {
+    Io(io::Error),
+    Write(object::write::Error),
+    Format(relf::Error),
+    InvalidContext(relf::Context),
+    UnsupportedABI(u32),
+    Corrupted { expected: u32, calculated: u32 },
+    Unsigned,
+    InvalidKey(String),
+    KeyExists(String),
+    Manifest(String, String),
+    Symbols(String, pdb::Error)
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::Io(e) => write!(f, "{e}"),
+            Error::Write(e) => write!(f, "Failed to build ELF file: {e}"),
+            Error::Format(relf::Error::Untrusted) => write!(f, "Bundle is signed by an untrusted key"),
+            Error::Format(relf::Error::BadSignature) => write!(f, "Bundle signature is invalid"),
+            Error::Format(relf::Error::Decompress) => write!(f, "Bundle payload could not be decompressed"),
+            Error::Format(relf::Error::InvalidElf) => write!(f, "Not a valid ELF file"),
+            Error::Format(relf::Error::NotRusset) => write!(f, "Not a Russet bundle"),
+            Error::Format(relf::Error::SectionNotFound(name)) => write!(f, "Section {name} not found"),
+            Error::Format(relf::Error::TooLarge) => write!(f, "Image is too large for a bundle"),
+            Error::Format(e) => write!(f, "Malformed bundle header: {e:?}"),
+            Error::InvalidContext(context) => write!(f, "Bundle was built for the {context:?} context"),
+            Error::UnsupportedABI(abi) => write!(f, "ABI version {abi} is not supported"),
+            Error::Corrupted { expected, calculated } => write!(f, "Checksum mismatch (expected {expected:#010x}, calculated {calculated:#010x})"),
+            Error::Unsigned => write!(f, "Bundle is not signed"),
+            Error::InvalidKey(path) => write!(f, "{path} is not a valid Ed25519 key"),
+            Error::KeyExists(path) => write!(f, "{path} already exists, refusing to overwrite it"),
+            Error::Manifest(path, e) => write!(f, "{path}: {e}"),
+            Error::Symbols(path, e) => write!(f, "{path}: {e}")
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl From<io::Error> for Error {
+    fn from(value: io::Error) -> Self {
+        Self::Io(value)
+    }
+}
+
+impl From<object::write::Error> for Error {
+    fn from(value: object::write::Error) -> Self {
+        Self::Write(value)
+    }
+}
+
+impl From<relf::Error> for Error {
+    fn from(value: relf::Error) -> Self {
+        Self::Format(value)
+    }
+}
@@ -1,44 +1,193 @@
 use std::fs;
-use std::fs::File;
-use std::io::Write;
 use object::{Architecture, BinaryFormat, Endianness, SectionKind};
 use object::write::Object;
+use pdb::FallibleIterator;
+use relf::{Codec, Context, Header, Metadata, Signature, Symbol, SymbolTable, ABI_VERSION, SUPPORTED_ABI};
 
-pub fn pe_to_elf(name: &str, context: u32) {
-    let original = fs::read(name).unwrap();
+pub use crate::error::Error;
+pub use ed25519_compact::SecretKey;
+
+mod error;
+
+pub struct Bundle {
+    pub header: Header,
+    pub payload: Vec<u8>,
+    pub signature: Option<Signature>,
+    pub metadata: Option<Metadata>,
+    pub symbols: Vec<u8>
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Verification {
+    Trusted,
+    SignatureNotChecked
+}
+
+pub struct PackOptions<'a> {
+    pub context: Context,
+    pub abi_version: u32,
+    pub codec: Codec,
+    pub key: Option<&'a SecretKey>,
+    pub metadata: Option<Metadata>,
+    pub symbols: Option<Vec<u8>>
+}
+
+impl PackOptions<'_> {
+    pub fn new(context: Context) -> Self {
+        Self {
+            context,
+            abi_version: ABI_VERSION,
+            codec: Codec::None,
+            key: None,
+            metadata: None,
+            symbols: None
+        }
+    }
+}
+
+pub fn pack(pe: &[u8], options: &PackOptions) -> Result<Vec<u8>, Error> {
     let mut object = Object::new(BinaryFormat::Elf, Architecture::X86_64, Endianness::Little);
 
-    let section_id = object.add_section(Vec::new(), b".note.tag".to_vec(), SectionKind::Note);
+    let header = Header::new(options.context, options.abi_version, options.codec, pe)?;
+    let payload = header.compress(pe);
 
-    let note_name = b"Russet \0";
-    let note_type = 1u32;
+    let mut notes = header.to_note();
+    if let Some(metadata) = &options.metadata {
+        notes.extend_from_slice(&metadata.to_note());
+    }
 
-    let abi_version = 2u32;
+    let section_id = object.add_section(Vec::new(), relf::NOTE_SECTION.as_bytes().to_vec(), SectionKind::Note);
+    object.section_mut(section_id).set_data(notes, 4);
 
-    let crc: crc::Crc<u32> = crc::Crc::<u32>::new(&crc::CRC_32_BZIP2);
-    let checksum = crc.checksum(&original);
+    let section_id = object.add_section(Vec::new(), relf::PAYLOAD_SECTION.as_bytes().to_vec(), SectionKind::Text);
+    object.section_mut(section_id).set_data(payload.clone(), 4);
 
-    let name_len = note_name.len() as u32;
-    let desc_len = 16u32;
+    let section_id = object.add_section(Vec::new(), relf::DEBUG_SECTION.as_bytes().to_vec(), SectionKind::Debug);
+    object.section_mut(section_id).set_data(options.symbols.clone().unwrap_or_else(|| SymbolTable::encode(&[])), 4);
 
-    let mut content = Vec::new();
-    content.extend_from_slice(&name_len.to_le_bytes());
-    content.extend_from_slice(&desc_len.to_le_bytes());
-    content.extend_from_slice(&note_type.to_le_bytes());
-    content.extend_from_slice(note_name);
-    content.extend_from_slice(&0u32.to_le_bytes());
-    content.extend_from_slice(&context.to_le_bytes());
-    content.extend_from_slice(&abi_version.to_le_bytes());
-    content.extend_from_slice(&checksum.to_le_bytes());
+    if let Some(key) = options.key {
+        let signature = Signature {
+            public_key: *key.public_key(),
+            signature: *key.sign(Signature::message(&header, &payload), None)
+        };
 
-    object.section_mut(section_id).set_data(content, 4);
+        let section_id = object.add_section(Vec::new(), relf::SIGNATURE_SECTION.as_bytes().to_vec(), SectionKind::Note);
+        object.section_mut(section_id).set_data(signature.to_note(), 4);
+    }
 
-    let section_id = object.add_section(Vec::new(), b".text".to_vec(), SectionKind::Text);
-    object.section_mut(section_id).set_data(original, 4);
+    Ok(object.write()?)
+}
+
+pub fn read(data: &[u8]) -> Result<Bundle, Error> {
+    let bundle = relf::Bundle::parse(data)?;
+
+    Ok(Bundle {
+        header: bundle.header,
+        payload: bundle.payload.to_vec(),
+        signature: bundle.signature,
+        metadata: bundle.metadata,
+        symbols: bundle.symbols.map(Vec::from).unwrap_or_default()
+    })
+}
+
+impl Bundle {
+    pub fn pe(&self) -> Result<Vec<u8>, Error> {
+        Ok(self.header.decompress(&self.payload)?)
+    }
 
-    let section_id = object.add_section(Vec::new(), b".debug".to_vec(), SectionKind::Debug);
-    object.section_mut(section_id).set_data(&[1, 2, 3, 4], 4);
+    pub fn verify(&self, expected_context: Option<Context>, trusted_keys: &[[u8; 32]]) -> Result<Verification, Error> {
+        match expected_context {
+            Some(context) if context != self.header.context => return Err(Error::InvalidContext(self.header.context)),
+            _ if !SUPPORTED_ABI.contains(&self.header.abi_version) => return Err(Error::UnsupportedABI(self.header.abi_version)),
+            _ => ()
+        }
+
+        let verification = match (&self.signature, trusted_keys) {
+            (_, []) => Verification::SignatureNotChecked,
+            (Some(signature), _) => {
+                signature.verify(&self.header, &self.payload, trusted_keys)?;
+                Verification::Trusted
+            },
+            (None, _) => return Err(Error::Unsigned)
+        };
+
+        let calculated = relf::checksum(&self.pe()?);
+        if calculated != self.header.checksum {
+            return Err(Error::Corrupted {
+                expected: self.header.checksum,
+                calculated
+            });
+        }
+
+        Ok(verification)
+    }
+}
+
+pub fn generate_key() -> (Vec<u8>, Vec<u8>) {
+    let pair = ed25519_compact::KeyPair::generate();
+    (pair.sk.to_vec(), pair.pk.to_vec())
+}
+
+pub fn read_secret_key(path: &str) -> Result<SecretKey, Error> {
+    SecretKey::from_slice(&fs::read(path)?).map_err(|_| Error::InvalidKey(path.to_string()))
+}
+
+pub fn read_public_key(path: &str) -> Result<[u8; 32], Error> {
+    fs::read(path)?.try_into().map_err(|_| Error::InvalidKey(path.to_string()))
+}
+
+pub fn read_manifest(path: &str) -> Result<Metadata, Error> {
+    let manifest: toml::Table = fs::read_to_string(path)?.parse().map_err(|e| Error::Manifest(path.to_string(), format!("{e}")))?;
+    let package = manifest.get("package").and_then(|p| p.as_table())
+        .ok_or(Error::Manifest(path.to_string(), String::from("missing [package] table")))?;
+
+    let field = |name: &str| package.get(name).and_then(|v| v.as_str()).unwrap_or_default().to_string();
+
+    Ok(Metadata {
+        name: field("name"),
+        version: field("version"),
+        description: field("description"),
+        authors: package.get("authors").and_then(|a| a.as_array())
+            .map(|a| a.iter().filter_map(|v| v.as_str()).map(String::from).collect())
+            .unwrap_or_default()
+    })
+}
+
+pub fn read_symbols(path: &str) -> Result<Vec<u8>, Error> {
+    let error = |e: pdb::Error| Error::Symbols(path.to_string(), e);
+
+    let mut pdb = pdb::PDB::open(fs::File::open(path)?).map_err(error)?;
+    let address_map = pdb.address_map().map_err(error)?;
+    let debug_information = pdb.debug_information().map_err(error)?;
+
+    let mut names = Vec::new();
+    let mut modules = debug_information.modules().map_err(error)?;
+
+    while let Some(module) = modules.next().map_err(error)? {
+        let Some(info) = pdb.module_info(&module).map_err(error)? else {
+            continue;
+        };
+
+        let mut symbols = info.symbols().map_err(error)?;
+        while let Some(symbol) = symbols.next().map_err(error)? {
+            if let Ok(pdb::SymbolData::Procedure(procedure)) = symbol.parse() {
+                if let Some(rva) = procedure.offset.to_rva(&address_map) {
+                    let name = procedure.name.to_string();
+                    names.push((rva.0, procedure.len, format!("{:#}", rustc_demangle::demangle(&name))));
+                }
+            }
+        }
+    }
+
+    let symbols: Vec<Symbol> = names.iter()
+        .map(|(rva, size, name)| Symbol { name, rva: *rva, size: *size })
+        .collect();
+
+    Ok(SymbolTable::encode(&symbols))
+}
 
-    let mut file = File::create(name).unwrap();
-    file.write_all(&object.write().unwrap()).unwrap();
+pub fn pe_to_elf(name: &str, options: &PackOptions) -> Result<(), Error> {
+    let original = fs::read(name)?;
+    fs::write(name, pack(&original, options)?)?;
+    Ok(())
 }
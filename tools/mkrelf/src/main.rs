@@ -0,0 +1,215 @@
+use std::env;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::process::ExitCode;
+use mkrelf::{generate_key, pack, read, read_manifest, read_public_key, read_secret_key, read_symbols, Error, PackOptions, Verification};
+use relf::{Codec, Context, SymbolTable, ABI_VERSION};
+
+const USAGE: &str = "usage: mkrelf pack <input.efi> <output> --context <kernel|user> [--abi <version>] [--key <secret-key>] [--compress <none|lz4>]
+                   [--manifest <Cargo.toml>] [--symbols <file.pdb>]
+       mkrelf inspect <bundle>
+       mkrelf verify <bundle> [--context <kernel|user>] [--trust <public-key>]...
+       mkrelf unpack <bundle> <output.efi>
+       mkrelf keygen <secret-key> <public-key>
+
+verify exits with status 3 when no --trust key is given and the signature was not checked.";
+
+const SIGNATURE_NOT_CHECKED: u8 = 3;
+
+#[derive(Default)]
+struct Arguments {
+    names: Vec<String>,
+    context: Option<Context>,
+    abi_version: Option<u32>,
+    codec: Option<Codec>,
+    key: Option<String>,
+    manifest: Option<String>,
+    symbols: Option<String>,
+    trust: Vec<String>
+}
+
+fn parse_context(value: &str) -> Option<Context> {
+    match value.to_lowercase().as_str() {
+        "kernel" | "1" => Some(Context::Kernel),
+        "user" | "2" => Some(Context::User),
+        _ => None
+    }
+}
+
+fn parse_codec(value: &str) -> Option<Codec> {
+    match value.to_lowercase().as_str() {
+        "none" => Some(Codec::None),
+        "lz4" => Some(Codec::Lz4),
+        _ => None
+    }
+}
+
+fn parse_arguments(command: &str, args: &[String]) -> Result<Arguments, String> {
+    let allowed: &[&str] = match command {
+        "pack" => &["--context", "--abi", "--key", "--compress", "--manifest", "--symbols"],
+        "verify" => &["--context", "--trust"],
+        _ => &[]
+    };
+
+    let mut parsed = Arguments::default();
+    let mut args = args.iter();
+
+    while let Some(arg) = args.next() {
+        let (name, inline) = match arg.split_once('=') {
+            Some((name, value)) => (name, Some(value.to_string())),
+            None => (arg.as_str(), None)
+        };
+
+        if name.starts_with("--") && !allowed.contains(&name) {
+            return Err(format!("unknown option {name} for {command}"));
+        }
+
+        let mut value = |name: &str| inline.clone()
+            .or_else(|| args.next().cloned())
+            .ok_or(format!("missing value for {name}"));
+
+        match name {
+            "--context" => {
+                let context = value(name)?;
+                parsed.context = Some(parse_context(&context).ok_or(format!("unknown context {context}"))?);
+            },
+            "--abi" => {
+                let abi = value(name)?;
+                parsed.abi_version = Some(abi.parse().map_err(|_| format!("invalid ABI version {abi}"))?);
+            },
+            "--compress" => {
+                let codec = value(name)?;
+                parsed.codec = Some(parse_codec(&codec).ok_or(format!("unknown codec {codec}"))?);
+            },
+            "--key" => parsed.key = Some(value(name)?),
+            "--manifest" => parsed.manifest = Some(value(name)?),
+            "--symbols" => parsed.symbols = Some(value(name)?),
+            "--trust" => parsed.trust.push(value(name)?),
+            _ => parsed.names.push(arg.clone())
+        }
+    }
+
+    Ok(parsed)
+}
+
+fn pack_bundle(input: &str, output: &str, context: Context, args: &Arguments) -> Result<(), Error> {
+    let key = args.key.as_deref().map(read_secret_key).transpose()?;
+    let pe = fs::read(input)?;
+    let options = PackOptions {
+        context,
+        abi_version: args.abi_version.unwrap_or(ABI_VERSION),
+        codec: args.codec.unwrap_or(Codec::None),
+        key: key.as_ref(),
+        metadata: args.manifest.as_deref().map(read_manifest).transpose()?,
+        symbols: args.symbols.as_deref().map(read_symbols).transpose()?
+    };
+    fs::write(output, pack(&pe, &options)?)?;
+    Ok(())
+}
+
+fn inspect_bundle(path: &str) -> Result<(), Error> {
+    let bundle = read(&fs::read(path)?)?;
+    println!("Header version: {}", bundle.header.version);
+    println!("Context:        {:?} ({})", bundle.header.context, bundle.header.context as u32);
+    println!("ABI version:    {}", bundle.header.abi_version);
+    println!("Checksum:       {:#010x}", bundle.header.checksum);
+    println!("Compression:    {:?}", bundle.header.codec);
+    match bundle.header.codec {
+        Codec::None => println!("Payload size:   {} bytes", bundle.payload.len()),
+        _ => println!("Payload size:   {} bytes ({} bytes uncompressed)", bundle.payload.len(), bundle.header.size)
+    }
+    if let Some(metadata) = bundle.metadata {
+        println!("Name:           {}", metadata.name);
+        println!("Version:        {}", metadata.version);
+        println!("Description:    {}", metadata.description);
+        println!("Authors:        {}", metadata.authors.join(", "));
+    }
+    match SymbolTable::parse(&bundle.symbols) {
+        Ok(symbols) => println!("Symbols:        {}", symbols.len()),
+        Err(_) => println!("Symbols:        (none)")
+    }
+    match bundle.signature {
+        Some(signature) => println!("Signed by:      {}", signature.public_key.iter().map(|b| format!("{b:02x}")).collect::<String>()),
+        None => println!("Signed by:      (unsigned)")
+    }
+    Ok(())
+}
+
+fn verify_bundle(path: &str, args: &Arguments) -> Result<ExitCode, Error> {
+    let trusted_keys = args.trust.iter().map(|path| read_public_key(path)).collect::<Result<Vec<_>, _>>()?;
+    match read(&fs::read(path)?)?.verify(args.context, &trusted_keys)? {
+        Verification::Trusted => {
+            println!("{path}: OK");
+            Ok(ExitCode::SUCCESS)
+        },
+        Verification::SignatureNotChecked => {
+            println!("{path}: OK, signature not checked (no --trust key given)");
+            Ok(ExitCode::from(SIGNATURE_NOT_CHECKED))
+        }
+    }
+}
+
+fn unpack_bundle(path: &str, output: &str) -> Result<(), Error> {
+    let bundle = read(&fs::read(path)?)?;
+    fs::write(output, bundle.pe()?)?;
+    Ok(())
+}
+
+fn keygen(secret: &str, public: &str) -> Result<(), Error> {
+    let (secret_key, public_key) = generate_key();
+    let mut options = OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+
+    match options.open(secret) {
+        Ok(mut file) => file.write_all(&secret_key)?,
+        Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => return Err(Error::KeyExists(secret.to_string())),
+        Err(e) => return Err(e.into())
+    }
+
+    fs::write(public, public_key)?;
+    Ok(())
+}
+
+fn run(command: &str, args: &Arguments) -> Option<Result<ExitCode, Error>> {
+    let result = match (command, args.names.as_slice(), args.context) {
+        ("pack", [input, output], Some(context)) => pack_bundle(input, output, context, args),
+        ("inspect", [path], _) => inspect_bundle(path),
+        ("verify", [path], _) => return Some(verify_bundle(path, args)),
+        ("unpack", [path, output], _) => unpack_bundle(path, output),
+        ("keygen", [secret, public], _) => keygen(secret, public),
+        _ => return None
+    };
+
+    Some(result.map(|_| ExitCode::SUCCESS))
+}
+
+fn main() -> ExitCode {
+    let args: Vec<String> = env::args().skip(1).collect();
+
+    let Some((command, rest)) = args.split_first() else {
+        eprintln!("{USAGE}");
+        return ExitCode::from(2);
+    };
+
+    let parsed = match parse_arguments(command, rest) {
+        Ok(parsed) => parsed,
+        Err(e) => {
+            eprintln!("error: {e}\n{USAGE}");
+            return ExitCode::from(2);
+        }
+    };
+
+    match run(command, &parsed) {
+        Some(Ok(status)) => status,
+        Some(Err(e)) => {
+            eprintln!("error: {e}");
+            ExitCode::FAILURE
+        },
+        None => {
+            eprintln!("{USAGE}");
+            ExitCode::from(2)
+        }
+    }
+}
@@ -0,0 +1,32 @@
+use mkrelf::{generate_key, pack, read, Error, PackOptions, SecretKey, Verification};
+use relf::Context;
+
+const PE: &[u8] = b"MZ\x90\x00 not really a PE image";
+
+fn key() -> (SecretKey, [u8; 32]) {
+    let (secret, public) = generate_key();
+    (SecretKey::from_slice(&secret).unwrap(), public.try_into().unwrap())
+}
+
+#[test]
+fn verify_without_trusted_keys_reports_signature_not_checked() {
+    let bundle = pack(PE, &PackOptions::new(Context::User)).unwrap();
+    assert_eq!(read(&bundle).unwrap().verify(None, &[]).unwrap(), Verification::SignatureNotChecked);
+}
+
+#[test]
+fn verify_with_trusted_key() {
+    let (secret, public) = key();
+    let options = PackOptions { key: Some(&secret), ..PackOptions::new(Context::User) };
+    let bundle = read(&pack(PE, &options).unwrap()).unwrap();
+
+    assert_eq!(bundle.verify(Some(Context::User), &[public]).unwrap(), Verification::Trusted);
+    assert!(matches!(bundle.verify(Some(Context::Kernel), &[public]), Err(Error::InvalidContext(Context::User))));
+    assert!(matches!(bundle.verify(None, &[key().1]), Err(Error::Format(relf::Error::Untrusted))));
+}
+
+#[test]
+fn verify_rejects_unsigned_bundle_when_keys_are_given() {
+    let bundle = read(&pack(PE, &PackOptions::new(Context::User)).unwrap()).unwrap();
+    assert!(matches!(bundle.verify(None, &[key().1]), Err(Error::Unsigned)));
+}
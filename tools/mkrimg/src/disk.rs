@@ -0,0 +1,293 @@
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use crc::{Crc, CRC_32_ISO_HDLC};
+use fatfs::{Dir, FatType, FileSystem, FormatVolumeOptions, FsOptions, ReadWriteSeek};
+use crate::error::Error;
+
+const SECTOR_SIZE: u64 = 512;
+const ENTRY_COUNT: u64 = 128;
+const ENTRY_SIZE: u64 = 128;
+const ENTRY_SECTORS: u64 = ENTRY_COUNT * ENTRY_SIZE / SECTOR_SIZE;
+const PARTITION_START: u64 = 2048;
+const HEADER_SIZE: usize = 92;
+pub const MIN_SIZE_MIB: u64 = 40;
+
+const EFI_SYSTEM_PARTITION: [u8; 16] = [
+    0x28, 0x73, 0x2a, 0xc1, 0x1f, 0xf8, 0xd2, 0x11, 0xba, 0x4b, 0x00, 0xa0, 0xc9, 0x3e, 0xc9, 0x3b
+];
+
+#[derive(Debug)]
+pub struct Disk {
+    pub path: PathBuf,
+    pub size_mib: u64,
+    pub label: String
+}
+
+struct Partition<'a> {
+    file: &'a mut File,
+    start: u64,
+    size: u64,
+    position: u64
+}
+
+impl<'a> Partition<'a> {
+    fn new(file: &'a mut File, first: u64, last: u64) -> Self {
+        Self { file, start: first * SECTOR_SIZE, size: (last - first + 1) * SECTOR_SIZE, position: 0 }
+    }
+}
+
+impl Read for Partition<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let len = buf.len().min(self.size.saturating_sub(self.position) as usize);
+        self.file.seek(SeekFrom::Start(self.start + self.position))?;
+        let read = self.file.read(&mut buf[..len])?;
+        self.position += read as u64;
+        Ok(read)
+    }
+}
+
+impl Write for Partition<'_> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let len = buf.len().min(self.size.saturating_sub(self.position) as usize);
+        if len == 0 && !buf.is_empty() {
+            return Err(io::Error::new(io::ErrorKind::WriteZero, "write past the end of the partition"));
+        }
+        self.file.seek(SeekFrom::Start(self.start + self.position))?;
+        let written = self.file.write(&buf[..len])?;
+        self.position += written as u64;
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.file.flush()
+    }
+}
+
+impl Seek for Partition<'_> {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        let position = match pos {
+            SeekFrom::Start(offset) => Some(offset),
+            SeekFrom::End(offset) => self.size.checked_add_signed(offset),
+            SeekFrom::Current(offset) => self.position.checked_add_signed(offset)
+        };
+
+        match position {
+            Some(position) if position <= self.size => {
+                self.position = position;
+                Ok(position)
+            },
+            _ => Err(io::Error::new(io::ErrorKind::InvalidInput, "seek outside of the partition"))
+        }
+    }
+}
+
+fn guid(seed: &[u8], index: u8) -> [u8; 16] {
+    let crc = Crc::<u32>::new(&CRC_32_ISO_HDLC);
+    let mut guid = [0u8; 16];
+
+    for (i, chunk) in guid.chunks_exact_mut(4).enumerate() {
+        let mut digest = crc.digest();
+        digest.update(seed);
+        digest.update(&[index, i as u8]);
+        chunk.copy_from_slice(&digest.finalize().to_le_bytes());
+    }
+
+    guid[7] = (guid[7] & 0x0f) | 0x40;
+    guid[8] = (guid[8] & 0x3f) | 0x80;
+    guid
+}
+
+fn protective_mbr(sectors: u64) -> [u8; SECTOR_SIZE as usize] {
+    let mut mbr = [0u8; SECTOR_SIZE as usize];
+    let entry = &mut mbr[446..462];
+    entry[1..4].copy_from_slice(&[0x00, 0x02, 0x00]);
+    entry[4] = 0xee;
+    entry[5..8].copy_from_slice(&[0xff, 0xff, 0xff]);
+    entry[8..12].copy_from_slice(&1u32.to_le_bytes());
+    entry[12..16].copy_from_slice(&((sectors - 1).min(u32::MAX as u64) as u32).to_le_bytes());
+    mbr[510..512].copy_from_slice(&[0x55, 0xaa]);
+    mbr
+}
+
+fn gpt_header(current: u64, backup: u64, entries: u64, sectors: u64, disk_guid: &[u8; 16], entries_crc: u32) -> [u8; SECTOR_SIZE as usize] {
+    let mut header = [0u8; SECTOR_SIZE as usize];
+    header[0..8].copy_from_slice(b"EFI PART");
+    header[8..12].copy_from_slice(&0x0001_0000u32.to_le_bytes());
+    header[12..16].copy_from_slice(&(HEADER_SIZE as u32).to_le_bytes());
+    header[24..32].copy_from_slice(&current.to_le_bytes());
+    header[32..40].copy_from_slice(&backup.to_le_bytes());
+    header[40..48].copy_from_slice(&(2 + ENTRY_SECTORS).to_le_bytes());
+    header[48..56].copy_from_slice(&(sectors - 2 - ENTRY_SECTORS).to_le_bytes());
+    header[56..72].copy_from_slice(disk_guid);
+    header[72..80].copy_from_slice(&entries.to_le_bytes());
+    header[80..84].copy_from_slice(&(ENTRY_COUNT as u32).to_le_bytes());
+    header[84..88].copy_from_slice(&(ENTRY_SIZE as u32).to_le_bytes());
+    header[88..92].copy_from_slice(&entries_crc.to_le_bytes());
+
+    let crc = Crc::<u32>::new(&CRC_32_ISO_HDLC).checksum(&header[..HEADER_SIZE]);
+    header[16..20].copy_from_slice(&crc.to_le_bytes());
+    header
+}
+
+fn partition_entries(first: u64, last: u64, partition_guid: &[u8; 16]) -> Vec<u8> {
+    let mut entries = vec![0u8; (ENTRY_COUNT * ENTRY_SIZE) as usize];
+    let entry = &mut entries[..ENTRY_SIZE as usize];
+    entry[0..16].copy_from_slice(&EFI_SYSTEM_PARTITION);
+    entry[16..32].copy_from_slice(partition_guid);
+    entry[32..40].copy_from_slice(&first.to_le_bytes());
+    entry[40..48].copy_from_slice(&last.to_le_bytes());
+
+    for (i, unit) in "EFI System Partition".encode_utf16().enumerate() {
+        entry[56 + i * 2..58 + i * 2].copy_from_slice(&unit.to_le_bytes());
+    }
+
+    entries
+}
+
+fn write_at(file: &mut File, sector: u64, data: &[u8]) -> io::Result<()> {
+    file.seek(SeekFrom::Start(sector * SECTOR_SIZE))?;
+    file.write_all(data)
+}
+
+fn copy_tree<T: ReadWriteSeek>(source: &Path, target: &Dir<'_, T>) -> io::Result<()> {
+    let mut entries = fs::read_dir(source)?.collect::<Result<Vec<_>, _>>()?;
+    entries.sort_by_key(|entry| entry.file_name());
+
+    for entry in entries {
+        let name = entry.file_name().to_string_lossy().into_owned();
+
+        if entry.file_type()?.is_dir() {
+            copy_tree(&entry.path(), &target.create_dir(&name)?)?;
+        } else {
+            target.create_file(&name)?.write_all(&fs::read(entry.path())?)?;
+        }
+    }
+
+    Ok(())
+}
+
+pub fn label(value: &str) -> Option<[u8; 11]> {
+    if value.is_empty() || value.len() > 11 || !value.bytes().all(|b| b.is_ascii_alphanumeric() || b"_- ".contains(&b)) {
+        return None;
+    }
+
+    let mut label = [b' '; 11];
+    label[..value.len()].copy_from_slice(value.to_ascii_uppercase().as_bytes());
+    Some(label)
+}
+
+pub fn write(disk: &Disk, tree: &Path) -> Result<(), Error> {
+    let error = |e| Error::Io(disk.path.clone(), e);
+
+    let sectors = disk.size_mib * 1024 * 1024 / SECTOR_SIZE;
+    let first = PARTITION_START;
+    let last = (sectors - 2 - ENTRY_SECTORS) / PARTITION_START * PARTITION_START - 1;
+    let seed = format!("{}:{}", disk.label, disk.size_mib);
+    let disk_guid = guid(seed.as_bytes(), 0);
+    let partition_guid = guid(seed.as_bytes(), 1);
+
+    let mut file = OpenOptions::new().read(true).write(true).create(true).truncate(true).open(&disk.path).map_err(error)?;
+    file.set_len(sectors * SECTOR_SIZE).map_err(error)?;
+
+    let entries = partition_entries(first, last, &partition_guid);
+    let entries_crc = Crc::<u32>::new(&CRC_32_ISO_HDLC).checksum(&entries);
+    let backup = sectors - 1;
+    let backup_entries = backup - ENTRY_SECTORS;
+
+    write_at(&mut file, 0, &protective_mbr(sectors)).map_err(error)?;
+    write_at(&mut file, 1, &gpt_header(1, backup, 2, sectors, &disk_guid, entries_crc)).map_err(error)?;
+    write_at(&mut file, 2, &entries).map_err(error)?;
+    write_at(&mut file, backup_entries, &entries).map_err(error)?;
+    write_at(&mut file, backup, &gpt_header(backup, 1, backup_entries, sectors, &disk_guid, entries_crc)).map_err(error)?;
+
+    let options = FormatVolumeOptions::new()
+        .fat_type(FatType::Fat32)
+        .volume_id(u32::from_le_bytes(partition_guid[..4].try_into().unwrap()))
+        .volume_label(label(&disk.label).unwrap());
+
+    fatfs::format_volume(Partition::new(&mut file, first, last), options).map_err(error)?;
+
+    let volume = FileSystem::new(Partition::new(&mut file, first, last), FsOptions::new()).map_err(error)?;
+    copy_tree(tree, &volume.root_dir()).map_err(error)?;
+    volume.unmount().map_err(error)?;
+
+    file.sync_all().map_err(error)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+    use std::process;
+
+    fn u32_at(data: &[u8], offset: usize) -> u32 {
+        u32::from_le_bytes(data[offset..offset + 4].try_into().unwrap())
+    }
+
+    fn u64_at(data: &[u8], offset: usize) -> u64 {
+        u64::from_le_bytes(data[offset..offset + 8].try_into().unwrap())
+    }
+
+    fn sector(image: &[u8], lba: u64) -> &[u8] {
+        &image[(lba * SECTOR_SIZE) as usize..((lba + 1) * SECTOR_SIZE) as usize]
+    }
+
+    fn check_header(image: &[u8], lba: u64, backup: u64) -> (u64, u64) {
+        let crc = Crc::<u32>::new(&CRC_32_ISO_HDLC);
+        let header = sector(image, lba);
+        assert_eq!(&header[0..8], b"EFI PART");
+
+        let mut copy = header[..HEADER_SIZE].to_vec();
+        copy[16..20].fill(0);
+        assert_eq!(u32_at(header, 16), crc.checksum(&copy));
+        assert_eq!((u64_at(header, 24), u64_at(header, 32)), (lba, backup));
+
+        let entries = u64_at(header, 72);
+        let entries = &image[(entries * SECTOR_SIZE) as usize..((entries + ENTRY_SECTORS) * SECTOR_SIZE) as usize];
+        assert_eq!(u32_at(header, 88), crc.checksum(entries));
+        assert_eq!(u64_at(entries, 32), PARTITION_START);
+        assert_eq!(&entries[0..16], &EFI_SYSTEM_PARTITION);
+
+        (u64_at(header, 40), u64_at(header, 48))
+    }
+
+    #[test]
+    fn writes_gpt_disk_with_fat_partition() {
+        let root = env::temp_dir().join(format!("mkrimg-disk-{}", process::id()));
+        let tree = root.join("esp");
+        fs::create_dir_all(tree.join("efi/boot")).unwrap();
+        fs::write(tree.join("efi/boot/bootx64.efi"), b"rouse").unwrap();
+
+        let disk = Disk { path: root.join("russet.img"), size_mib: MIN_SIZE_MIB, label: String::from("Russet") };
+        write(&disk, &tree).unwrap();
+        let image = fs::read(&disk.path).unwrap();
+        let sectors = image.len() as u64 / SECTOR_SIZE;
+        assert_eq!(sectors, MIN_SIZE_MIB * 1024 * 1024 / SECTOR_SIZE);
+
+        let mbr = sector(&image, 0);
+        assert_eq!(&mbr[510..512], &[0x55, 0xaa]);
+        assert_eq!(mbr[446 + 4], 0xee);
+        assert_eq!((u32_at(mbr, 446 + 8), u32_at(mbr, 446 + 12)), (1, sectors as u32 - 1));
+
+        let usable = check_header(&image, 1, sectors - 1);
+        assert_eq!(check_header(&image, sectors - 1, 1), usable);
+        assert_eq!(u64_at(sector(&image, sectors - 1), 72), sectors - 1 - ENTRY_SECTORS);
+
+        let entry = sector(&image, 2);
+        let (first, last) = (u64_at(entry, 32), u64_at(entry, 40));
+        assert!(first >= usable.0 && last <= usable.1 && first < last);
+
+        let mut file = File::open(&disk.path).unwrap();
+        let volume = FileSystem::new(Partition::new(&mut file, first, last), FsOptions::new()).unwrap();
+        assert_eq!(volume.fat_type(), FatType::Fat32);
+        assert_eq!(volume.volume_label(), "RUSSET");
+
+        let mut data = Vec::new();
+        volume.root_dir().open_file("efi/boot/bootx64.efi").unwrap().read_to_end(&mut data).unwrap();
+        assert_eq!(data, b"rouse");
+
+        drop(volume);
+        fs::remove_dir_all(root).unwrap();
+    }
+}
@@ -0,0 +1,30 @@
+use std::fmt::{Display, Formatter};
+use std::io;
+use std::path::PathBuf;
+
+#[derive(Debug)]
+pub enum Error {
+    Io(PathBuf, io::Error),
+    Manifest(PathBuf, String),
+    MissingArtifacts(Vec<PathBuf>),
+    Bundle(PathBuf, mkrelf::Error)
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::Io(path, e) => write!(f, "{}: {e}", path.display()),
+            Error::Manifest(path, e) => write!(f, "{}: {e}", path.display()),
+            Error::MissingArtifacts(paths) => {
+                write!(f, "{} artifact(s) not found, build the system before creating the image:", paths.len())?;
+                for path in paths {
+                    write!(f, "\n  {}", path.display())?;
+                }
+                Ok(())
+            },
+            Error::Bundle(path, e) => write!(f, "{}: {e}", path.display())
+        }
+    }
+}
+
+impl std::error::Error for Error {}
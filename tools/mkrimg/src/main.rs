@@ -1,64 +1,115 @@
+mod disk;
+mod error;
+mod manifest;
+
+use std::env;
 use std::fs;
-use std::path::PathBuf;
-use mkrelf::pe_to_elf;
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+use mkrelf::{pack, read_manifest, read_secret_key, read_symbols, PackOptions, SecretKey};
+use crate::error::Error;
+use crate::manifest::{Artifact, Bundle, Manifest};
 
-fn place(source: &str, target: &str) {
-    println!("{source}");
+const DEFAULT_MANIFEST: &str = "./image.toml";
 
-    if PathBuf::from(target).exists() {
-        fs::remove_file(target).unwrap();
-    }
+fn place(source: &Path, destination: &Path, data: &[u8]) -> Result<(), Error> {
+    println!("{}", source.display());
 
-    if !PathBuf::from(source).exists() {
-        panic!("error: File {source} not found.");
+    if let Some(directory) = destination.parent() {
+        fs::create_dir_all(directory).map_err(|e| Error::Io(directory.to_path_buf(), e))?;
     }
 
-    fs::copy(source, target).unwrap();
+    fs::write(destination, data).map_err(|e| Error::Io(destination.to_path_buf(), e))
 }
 
-fn dir(name: &str) {
-    if PathBuf::from(name).exists() {
-        return;
-    }
-
-    fs::create_dir_all(name).unwrap();
+fn symbols_path(source: &Path) -> PathBuf {
+    let symbols = source.with_extension("pdb");
+    symbols.with_file_name(symbols.file_name().unwrap().to_string_lossy().replace('-', "_"))
 }
 
-fn create_bundle(source: &str, directory: &str, destination: &str, ctx: u32) {
-    dir(directory);
-    place(source, &format!("{destination}"));
-    pe_to_elf(&format!("{destination}"), ctx);
+fn create_bundle(artifact: &Artifact, bundle: &Bundle, key: &SecretKey, pe: &[u8]) -> Result<Vec<u8>, Error> {
+    let metadata = match &bundle.package {
+        Some(package) => Some(read_manifest(&package.to_string_lossy()).map_err(|e| Error::Bundle(package.clone(), e))?),
+        None => None
+    };
+
+    let symbols = symbols_path(&artifact.source);
+    let symbols = match read_symbols(&symbols.to_string_lossy()) {
+        Ok(symbols) => Some(symbols),
+        Err(e) => {
+            println!("warning: {}: {e}", symbols.display());
+            None
+        }
+    };
+
+    let options = PackOptions {
+        abi_version: bundle.abi_version,
+        codec: bundle.codec,
+        key: Some(key),
+        metadata,
+        symbols,
+        ..PackOptions::new(bundle.context)
+    };
+
+    pack(pe, &options).map_err(|e| Error::Bundle(artifact.source.clone(), e))
 }
 
-fn include_program(source: &str, destination: &str) {
-    create_bundle(
-        &format!("./os/target/x86_64-unknown-uefi/debug/{source}.efi"),
-        "./esp/rootfs/System/Programs",
-        &format!("./esp/rootfs/System/Programs/{destination}"),
-        2
-    );
+fn build(manifest: &Manifest) -> Result<(), Error> {
+    let missing: Vec<PathBuf> = manifest.artifacts.iter()
+        .chain(&manifest.files)
+        .map(|artifact| &artifact.source)
+        .chain(manifest.artifacts.iter().filter_map(|artifact| artifact.bundle.as_ref()?.package.as_ref()))
+        .chain(&manifest.key)
+        .filter(|path| !path.is_file())
+        .cloned()
+        .collect();
+
+    if !missing.is_empty() {
+        return Err(Error::MissingArtifacts(missing));
+    }
+
+    let key = match &manifest.key {
+        Some(path) => Some(read_secret_key(&path.to_string_lossy()).map_err(|e| Error::Bundle(path.clone(), e))?),
+        None => None
+    };
+
+    for artifact in manifest.artifacts.iter().chain(&manifest.files) {
+        let data = fs::read(&artifact.source).map_err(|e| Error::Io(artifact.source.clone(), e))?;
+
+        let data = match (&artifact.bundle, &key) {
+            (Some(bundle), Some(key)) => create_bundle(artifact, bundle, key, &data)?,
+            _ => data
+        };
+
+        place(&artifact.source, &artifact.destination, &data)?;
+    }
+
+    if let Some(disk) = &manifest.disk {
+        println!("Writing {} ({} MiB, label {})", disk.path.display(), disk.size_mib, disk.label);
+        disk::write(disk, &manifest.output)?;
+    }
+
+    Ok(())
 }
 
-fn main() {
+fn main() -> ExitCode {
     println!("mkrimg - Generate a working Russet system image from compiled files");
 
-    dir("./esp/efi/boot");
-    place("./os/target/x86_64-unknown-uefi/debug/rouse.efi", "./esp/efi/boot/bootx64.efi");
-
-    create_bundle(
-        "./os/target/x86_64-unknown-uefi/debug/velm.efi",
-        "./esp/rootfs/System",
-        "./esp/rootfs/System/Kernel",
-        1
-    );
-
-    create_bundle(
-        "./os/target/x86_64-unknown-uefi/debug/sable.efi",
-        "./esp/rootfs/System",
-        "./esp/rootfs/System/Init",
-        1
-    );
-
-    include_program("demo", "DemoProgram");
-    include_program("command-interpreter", "CommandInterpreter");
+    let args: Vec<String> = env::args().skip(1).collect();
+    let path = match args.as_slice() {
+        [] => PathBuf::from(DEFAULT_MANIFEST),
+        [path] => PathBuf::from(path),
+        _ => {
+            eprintln!("usage: mkrimg [manifest]");
+            return ExitCode::from(2);
+        }
+    };
+
+    match manifest::read(&path).and_then(|manifest| build(&manifest)) {
+        Ok(_) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("error: {e}");
+            ExitCode::FAILURE
+        }
+    }
 }
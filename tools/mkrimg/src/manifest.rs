@@ -0,0 +1,301 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use relf::{Codec, Context, ABI_VERSION, SUPPORTED_ABI};
+use toml::{Table, Value};
+use crate::disk::{self, Disk, MIN_SIZE_MIB};
+use crate::error::Error;
+
+const DEFAULT_SIZE_MIB: u64 = 64;
+const DEFAULT_LABEL: &str = "RUSSET";
+
+#[derive(Debug)]
+pub struct Bundle {
+    pub context: Context,
+    pub abi_version: u32,
+    pub codec: Codec,
+    pub package: Option<PathBuf>
+}
+
+#[derive(Debug)]
+pub struct Artifact {
+    pub source: PathBuf,
+    pub destination: PathBuf,
+    pub bundle: Option<Bundle>
+}
+
+#[derive(Debug)]
+pub struct Manifest {
+    pub output: PathBuf,
+    pub key: Option<PathBuf>,
+    pub disk: Option<Disk>,
+    pub artifacts: Vec<Artifact>,
+    pub files: Vec<Artifact>
+}
+
+struct Entry<'a> {
+    path: &'a Path,
+    base: &'a Path,
+    section: String,
+    table: &'a Table
+}
+
+impl Entry<'_> {
+    fn error(&self, message: String) -> Error {
+        Error::Manifest(self.path.to_path_buf(), format!("{}: {message}", self.section))
+    }
+
+    fn string(&self, name: &str) -> Result<Option<&str>, Error> {
+        match self.table.get(name) {
+            Some(Value::String(value)) => Ok(Some(value)),
+            Some(_) => Err(self.error(format!("{name} must be a string"))),
+            None => Ok(None)
+        }
+    }
+
+    fn required(&self, name: &str) -> Result<&str, Error> {
+        self.string(name)?.ok_or_else(|| self.error(format!("missing {name}")))
+    }
+
+    fn path(&self, name: &str) -> Result<Option<PathBuf>, Error> {
+        Ok(self.string(name)?.map(|value| self.base.join(value)))
+    }
+
+    fn destination(&self, output: &Path) -> Result<PathBuf, Error> {
+        let destination = Path::new(self.required("destination")?);
+
+        if destination.is_absolute() || destination.components().any(|c| c.as_os_str() == "..") {
+            return Err(self.error(format!("destination {} must stay inside the image", destination.display())));
+        }
+
+        Ok(output.join(destination))
+    }
+
+    fn check(&self, allowed: &[&str]) -> Result<(), Error> {
+        match self.table.keys().find(|key| !allowed.contains(&key.as_str())) {
+            Some(key) => Err(self.error(format!("unknown key {key}"))),
+            None => Ok(())
+        }
+    }
+
+    fn bundle(&self) -> Result<Option<Bundle>, Error> {
+        let Some(context) = self.string("context")? else {
+            return match ["abi", "compress", "package"].iter().find(|key| self.table.contains_key(**key)) {
+                Some(key) => Err(self.error(format!("{key} requires a context"))),
+                None => Ok(None)
+            };
+        };
+
+        let context = match context {
+            "kernel" => Context::Kernel,
+            "user" => Context::User,
+            _ => return Err(self.error(format!("unknown context {context}")))
+        };
+
+        let abi_version = match self.table.get("abi") {
+            Some(Value::Integer(abi)) => u32::try_from(*abi)
+                .ok()
+                .filter(|abi| SUPPORTED_ABI.contains(abi))
+                .ok_or_else(|| self.error(format!("ABI version {abi} is not supported")))?,
+            Some(_) => return Err(self.error(String::from("abi must be an integer"))),
+            None => ABI_VERSION
+        };
+
+        let codec = match self.string("compress")? {
+            Some("none") | None => Codec::None,
+            Some("lz4") => Codec::Lz4,
+            Some(codec) => return Err(self.error(format!("unknown codec {codec}")))
+        };
+
+        Ok(Some(Bundle { context, abi_version, codec, package: self.path("package")? }))
+    }
+}
+
+fn tables<'a>(path: &Path, manifest: &'a Table, name: &str) -> Result<Vec<&'a Table>, Error> {
+    match manifest.get(name) {
+        Some(Value::Array(entries)) => entries.iter()
+            .map(|entry| entry.as_table().ok_or_else(|| Error::Manifest(path.to_path_buf(), format!("[[{name}]] must be a table"))))
+            .collect(),
+        Some(_) => Err(Error::Manifest(path.to_path_buf(), format!("{name} must be an array of tables"))),
+        None => Ok(Vec::new())
+    }
+}
+
+pub fn read(path: &Path) -> Result<Manifest, Error> {
+    let text = fs::read_to_string(path).map_err(|e| Error::Io(path.to_path_buf(), e))?;
+    parse(path, &text)
+}
+
+fn parse(path: &Path, text: &str) -> Result<Manifest, Error> {
+    let document: Table = text.parse().map_err(|e| Error::Manifest(path.to_path_buf(), format!("{e}")))?;
+    let base = path.parent().unwrap_or(Path::new("."));
+
+    let image = match document.get("image") {
+        Some(Value::Table(image)) => image,
+        _ => return Err(Error::Manifest(path.to_path_buf(), String::from("missing [image] table")))
+    };
+
+    if let Some(key) = document.keys().find(|key| !["image", "disk", "artifact", "file"].contains(&key.as_str())) {
+        return Err(Error::Manifest(path.to_path_buf(), format!("unknown table {key}")));
+    }
+
+    let image = Entry { path, base, section: String::from("[image]"), table: image };
+    image.check(&["output", "artifacts", "key"])?;
+
+    let output = base.join(image.required("output")?);
+    let artifacts = base.join(image.required("artifacts")?);
+    let key = image.path("key")?;
+
+    let disk = match document.get("disk") {
+        Some(Value::Table(table)) => {
+            let entry = Entry { path, base, section: String::from("[disk]"), table };
+            entry.check(&["path", "size", "label"])?;
+
+            let size_mib = match table.get("size") {
+                Some(Value::Integer(size)) => u64::try_from(*size)
+                    .ok()
+                    .filter(|size| *size >= MIN_SIZE_MIB)
+                    .ok_or_else(|| entry.error(format!("size must be at least {MIN_SIZE_MIB} MiB")))?,
+                Some(_) => return Err(entry.error(String::from("size must be an integer"))),
+                None => DEFAULT_SIZE_MIB
+            };
+
+            let label = entry.string("label")?.unwrap_or(DEFAULT_LABEL);
+            if disk::label(label).is_none() {
+                return Err(entry.error(format!("{label} is not a valid volume label")));
+            }
+
+            Some(Disk { path: base.join(entry.required("path")?), size_mib, label: label.to_string() })
+        },
+        Some(_) => return Err(Error::Manifest(path.to_path_buf(), String::from("disk must be a table"))),
+        None => None
+    };
+
+    let mut manifest = Manifest { output, key, disk, artifacts: Vec::new(), files: Vec::new() };
+
+    for (i, table) in tables(path, &document, "artifact")?.into_iter().enumerate() {
+        let entry = Entry { path, base, section: format!("artifact #{}", i + 1), table };
+        entry.check(&["name", "destination", "context", "abi", "compress", "package"])?;
+
+        manifest.artifacts.push(Artifact {
+            source: artifacts.join(entry.required("name")?),
+            destination: entry.destination(&manifest.output)?,
+            bundle: entry.bundle()?
+        });
+    }
+
+    for (i, table) in tables(path, &document, "file")?.into_iter().enumerate() {
+        let entry = Entry { path, base, section: format!("file #{}", i + 1), table };
+        entry.check(&["source", "destination"])?;
+
+        manifest.files.push(Artifact {
+            source: base.join(entry.required("source")?),
+            destination: entry.destination(&manifest.output)?,
+            bundle: None
+        });
+    }
+
+    if manifest.key.is_none() && manifest.artifacts.iter().any(|artifact| artifact.bundle.is_some()) {
+        return Err(Error::Manifest(path.to_path_buf(), String::from("[image]: bundles require a signing key")));
+    }
+
+    let mut destinations: Vec<&PathBuf> = manifest.artifacts.iter().chain(&manifest.files).map(|a| &a.destination).collect();
+    destinations.sort();
+    if let Some(pair) = destinations.windows(2).find(|pair| pair[0] == pair[1]) {
+        return Err(Error::Manifest(path.to_path_buf(), format!("{} is written more than once", pair[0].display())));
+    }
+
+    Ok(manifest)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const IMAGE: &str = "[image]\noutput = \"esp\"\nartifacts = \"build\"\nkey = \"keys/developer.key\"\n";
+
+    fn parse_str(text: &str) -> Result<Manifest, Error> {
+        parse(Path::new("image.toml"), text)
+    }
+
+    fn error(text: &str) -> String {
+        parse_str(text).unwrap_err().to_string()
+    }
+
+    fn artifact(fields: &str) -> String {
+        format!("{IMAGE}[[artifact]]\nname = \"velm.efi\"\ndestination = \"rootfs/System/Kernel\"\n{fields}")
+    }
+
+    #[test]
+    fn reads_image_disk_and_entries() {
+        let manifest = parse_str(&format!("{}[disk]\npath = \"russet.img\"\n[[file]]\nsource = \"BootConfig\"\ndestination = \"rootfs/System/BootConfig\"\n",
+            artifact("context = \"kernel\"\nabi = 2\ncompress = \"lz4\"\n"))).unwrap();
+
+        assert_eq!(manifest.output, Path::new("esp"));
+        assert_eq!(manifest.key.as_deref(), Some(Path::new("keys/developer.key")));
+        let disk = manifest.disk.unwrap();
+        assert_eq!((disk.size_mib, disk.label.as_str()), (DEFAULT_SIZE_MIB, DEFAULT_LABEL));
+
+        let bundle = manifest.artifacts[0].bundle.as_ref().unwrap();
+        assert_eq!((bundle.context, bundle.abi_version, bundle.codec), (Context::Kernel, 2, Codec::Lz4));
+        assert_eq!(manifest.artifacts[0].source, Path::new("build/velm.efi"));
+        assert_eq!(manifest.files[0].destination, Path::new("esp/rootfs/System/BootConfig"));
+    }
+
+    #[test]
+    fn bundles_default_to_current_abi_without_compression() {
+        let manifest = parse_str(&artifact("context = \"user\"\n")).unwrap();
+        let bundle = manifest.artifacts[0].bundle.as_ref().unwrap();
+        assert_eq!((bundle.abi_version, bundle.codec), (ABI_VERSION, Codec::None));
+        assert!(parse_str(&artifact("")).unwrap().artifacts[0].bundle.is_none());
+    }
+
+    #[test]
+    fn rejects_unknown_keys_and_tables() {
+        assert_eq!(error("output = \"esp\""), "image.toml: missing [image] table");
+        assert_eq!(error(&format!("{IMAGE}[extra]\n")), "image.toml: unknown table extra");
+        assert_eq!(error(&format!("{IMAGE}compress = \"lz4\"\n")), "image.toml: [image]: unknown key compress");
+        assert_eq!(error(&artifact("signed = true\n")), "image.toml: artifact #1: unknown key signed");
+        assert_eq!(error(&format!("{IMAGE}[disk]\npath = \"a.img\"\nformat = \"vhd\"\n")), "image.toml: [disk]: unknown key format");
+        assert_eq!(error(&format!("artifact = 1\n{IMAGE}")), "image.toml: artifact must be an array of tables");
+    }
+
+    #[test]
+    fn rejects_destinations_outside_the_image() {
+        let text = format!("{IMAGE}[[file]]\nsource = \"a\"\ndestination = \"../a\"\n");
+        assert_eq!(error(&text), "image.toml: file #1: destination ../a must stay inside the image");
+        let text = format!("{IMAGE}[[file]]\nsource = \"a\"\ndestination = \"/a\"\n");
+        assert_eq!(error(&text), "image.toml: file #1: destination /a must stay inside the image");
+    }
+
+    #[test]
+    fn rejects_duplicate_destinations() {
+        let text = format!("{}[[file]]\nsource = \"Kernel\"\ndestination = \"rootfs/System/Kernel\"\n", artifact(""));
+        assert_eq!(error(&text), "image.toml: esp/rootfs/System/Kernel is written more than once");
+    }
+
+    #[test]
+    fn rejects_unsupported_bundle_settings() {
+        assert_eq!(error(&artifact("context = \"driver\"\n")), "image.toml: artifact #1: unknown context driver");
+        assert_eq!(error(&artifact("context = \"user\"\nabi = 99\n")), "image.toml: artifact #1: ABI version 99 is not supported");
+        assert_eq!(error(&artifact("context = \"user\"\nabi = \"2\"\n")), "image.toml: artifact #1: abi must be an integer");
+        assert_eq!(error(&artifact("context = \"user\"\ncompress = \"zstd\"\n")), "image.toml: artifact #1: unknown codec zstd");
+        assert_eq!(error(&artifact("compress = \"lz4\"\n")), "image.toml: artifact #1: compress requires a context");
+    }
+
+    #[test]
+    fn rejects_bundles_without_key() {
+        let text = "[image]\noutput = \"esp\"\nartifacts = \"build\"\n[[artifact]]\nname = \"sable.efi\"\ndestination = \"rootfs/System/Init\"\ncontext = \"kernel\"\n";
+        assert_eq!(error(text), "image.toml: [image]: bundles require a signing key");
+    }
+
+    #[test]
+    fn rejects_invalid_disk_settings() {
+        let disk = |fields: &str| format!("{IMAGE}[disk]\npath = \"russet.img\"\n{fields}");
+        assert_eq!(error(&disk("size = 8\n")), format!("image.toml: [disk]: size must be at least {MIN_SIZE_MIB} MiB"));
+        assert_eq!(error(&disk("size = -1\n")), format!("image.toml: [disk]: size must be at least {MIN_SIZE_MIB} MiB"));
+        assert_eq!(error(&disk("size = \"64\"\n")), "image.toml: [disk]: size must be an integer");
+        assert_eq!(error(&disk("label = \"TOO-LONG-LABEL\"\n")), "image.toml: [disk]: TOO-LONG-LABEL is not a valid volume label");
+        assert_eq!(error(&disk("label = \"RUSSET/OS\"\n")), "image.toml: [disk]: RUSSET/OS is not a valid volume label");
+        assert_eq!(parse_str(&disk("label = \"russet os\"\n")).unwrap().disk.unwrap().label, "russet os");
+    }
+}
@@ -0,0 +1,30 @@
+use std::fmt::{Display, Formatter};
+use std::io;
+use std::path::PathBuf;
+use std::process::ExitStatus;
+use crate::Outcome;
+
+#[derive(Debug)]
+pub enum Error {
+    Io(PathBuf, io::Error),
+    Build(ExitStatus),
+    Spawn(String, io::Error),
+    Qemu(io::Error),
+    Timeout(String, String),
+    Exited(Outcome, String, String)
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::Io(path, e) => write!(f, "{}: {e}", path.display()),
+            Error::Build(status) => write!(f, "build failed ({status})"),
+            Error::Spawn(program, e) => write!(f, "could not start {program}: {e}"),
+            Error::Qemu(e) => write!(f, "lost connection to QEMU: {e}"),
+            Error::Timeout(what, output) => write!(f, "timed out waiting for {what}, output:\n{output}"),
+            Error::Exited(outcome, what, output) => write!(f, "the system exited ({outcome:?}) while waiting for {what}, output:\n{output}")
+        }
+    }
+}
+
+impl std::error::Error for Error {}
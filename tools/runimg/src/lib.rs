@@ -0,0 +1,260 @@
+mod error;
+mod session;
+
+use std::env;
+use std::fs::{self, File};
+use std::io::{self, Read, Write};
+use std::path::{Path, PathBuf};
+use std::process::{Child, ChildStdin, Command, ExitStatus, Stdio};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+pub use error::Error;
+pub use session::Session;
+
+pub const DEBUG_EXIT_PORT: u16 = 0xf4;
+pub const STOP_EXIT_STATUS: u8 = 0x7e;
+pub const DEBUG_EXIT_OPTION: &str = "russet.debug-exit";
+
+pub(crate) const POLL_INTERVAL: Duration = Duration::from_millis(20);
+
+static INSTANCE: AtomicUsize = AtomicUsize::new(0);
+
+#[derive(Debug, Clone)]
+pub struct Options {
+    pub qemu: String,
+    pub image: PathBuf,
+    pub firmware: PathBuf,
+    pub memory: String,
+    pub log: Option<PathBuf>,
+    pub echo: bool,
+    pub interactive: bool,
+    pub snapshot: bool,
+    pub tftp: Option<PathBuf>,
+    pub extra: Vec<String>
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Self {
+            qemu: env::var("QEMU").unwrap_or(String::from("qemu-system-x86_64")),
+            image: PathBuf::from("./russet.img"),
+            firmware: PathBuf::from("./firmware"),
+            memory: String::from("1024M"),
+            log: Some(PathBuf::from("./russet.log")),
+            echo: true,
+            interactive: false,
+            snapshot: false,
+            tftp: None,
+            extra: Vec::new()
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Outcome {
+    Exited(u8),
+    PoweredOff,
+    Failed(Option<i32>),
+    Timeout
+}
+
+impl Outcome {
+    fn from_status(status: ExitStatus) -> Self {
+        Self::from_code(status.code())
+    }
+
+    fn from_code(code: Option<i32>) -> Self {
+        match code {
+            Some(0) => Outcome::PoweredOff,
+            Some(code) if code > 1 && code & 1 == 1 => Outcome::Exited(((code >> 1) - 1) as u8),
+            code => Outcome::Failed(code)
+        }
+    }
+
+    pub fn exit_code(&self) -> u8 {
+        match self {
+            Outcome::Exited(status) => *status,
+            Outcome::PoweredOff => 0,
+            Outcome::Failed(_) => 125,
+            Outcome::Timeout => 124
+        }
+    }
+}
+
+pub struct Machine {
+    child: Child,
+    stdin: Option<ChildStdin>,
+    vars: PathBuf,
+    output: Arc<Mutex<Vec<u8>>>,
+    reader: Option<JoinHandle<()>>,
+    outcome: Option<Outcome>
+}
+
+fn capture(mut source: impl Read, output: Arc<Mutex<Vec<u8>>>, mut log: Option<File>, echo: bool) {
+    let mut buf = [0u8; 4096];
+
+    loop {
+        let len = match source.read(&mut buf) {
+            Ok(0) | Err(_) => break,
+            Ok(len) => len
+        };
+
+        output.lock().unwrap().extend_from_slice(&buf[..len]);
+
+        if let Some(log) = &mut log {
+            let _ = log.write_all(&buf[..len]);
+        }
+
+        if echo {
+            let mut stdout = io::stdout();
+            let _ = stdout.write_all(&buf[..len]);
+            let _ = stdout.flush();
+        }
+    }
+}
+
+pub fn build(root: &Path) -> Result<(), Error> {
+    let script = root.join("build.sh");
+    let status = Command::new("bash")
+        .arg(&script)
+        .current_dir(root)
+        .status()
+        .map_err(|e| Error::Spawn(script.display().to_string(), e))?;
+
+    if status.success() {
+        Ok(())
+    } else {
+        Err(Error::Build(status))
+    }
+}
+
+impl Machine {
+    pub fn start(options: &Options) -> Result<Self, Error> {
+        if !options.image.is_file() {
+            return Err(Error::Io(options.image.clone(), io::Error::from(io::ErrorKind::NotFound)));
+        }
+
+        let log = match &options.log {
+            Some(path) => Some(File::create(path).map_err(|e| Error::Io(path.clone(), e))?),
+            None => None
+        };
+
+        let code = options.firmware.join("OVMF_CODE.fd");
+        let vars = env::temp_dir().join(format!("russet-vars-{}-{}.fd", std::process::id(), INSTANCE.fetch_add(1, Ordering::Relaxed)));
+        let template = options.firmware.join("OVMF_VARS.fd");
+        fs::copy(&template, &vars).map_err(|e| Error::Io(template, e))?;
+
+        let network = match &options.tftp {
+            Some(root) => vec![String::from("-nic"), format!("user,model=virtio-net-pci,tftp={}", root.display())],
+            None => Vec::new()
+        };
+
+        let mut child = Command::new(&options.qemu)
+            .arg("-m").arg(&options.memory)
+            .arg("-drive").arg(format!("if=pflash,format=raw,readonly=on,file={}", code.display()))
+            .arg("-drive").arg(format!("if=pflash,format=raw,file={}", vars.display()))
+            .arg("-drive").arg(format!("file={},format=raw,media=disk,snapshot={}", options.image.display(), if options.snapshot { "on" } else { "off" }))
+            .arg("-device").arg(format!("isa-debug-exit,iobase={DEBUG_EXIT_PORT:#x},iosize=0x04"))
+            .arg("-smbios").arg(format!("type=11,value={DEBUG_EXIT_OPTION}={DEBUG_EXIT_PORT:#x}"))
+            .args(["-display", "none", "-monitor", "none", "-serial", "stdio", "-no-reboot"])
+            .args(&network)
+            .args(&options.extra)
+            .stdin(if options.interactive { Stdio::inherit() } else { Stdio::piped() })
+            .stdout(Stdio::piped())
+            .stderr(Stdio::inherit())
+            .spawn()
+            .map_err(|e| {
+                let _ = fs::remove_file(&vars);
+                Error::Spawn(options.qemu.clone(), e)
+            })?;
+
+        let output = Arc::new(Mutex::new(Vec::new()));
+        let stdout = child.stdout.take().unwrap();
+        let reader = {
+            let output = output.clone();
+            let echo = options.echo;
+            thread::spawn(move || capture(stdout, output, log, echo))
+        };
+
+        Ok(Self { stdin: child.stdin.take(), child, vars, output, reader: Some(reader), outcome: None })
+    }
+
+    pub fn output(&self) -> Vec<u8> {
+        self.output.lock().unwrap().clone()
+    }
+
+    pub fn send(&mut self, data: &[u8]) -> Result<(), Error> {
+        let stdin = self.stdin.as_mut().ok_or(Error::Qemu(io::Error::from(io::ErrorKind::BrokenPipe)))?;
+        stdin.write_all(data).and_then(|_| stdin.flush()).map_err(Error::Qemu)
+    }
+
+    pub fn poll(&mut self) -> Result<Option<Outcome>, Error> {
+        if self.outcome.is_none() {
+            self.outcome = self.child.try_wait().map_err(Error::Qemu)?.map(Outcome::from_status);
+
+            if self.outcome.is_some() {
+                if let Some(reader) = self.reader.take() {
+                    let _ = reader.join();
+                }
+            }
+        }
+
+        Ok(self.outcome)
+    }
+
+    pub fn wait(&mut self, timeout: Option<Duration>) -> Result<Outcome, Error> {
+        let start = Instant::now();
+
+        loop {
+            if let Some(outcome) = self.poll()? {
+                return Ok(outcome);
+            }
+
+            if timeout.is_some_and(|timeout| start.elapsed() >= timeout) {
+                self.kill();
+                self.outcome = Some(Outcome::Timeout);
+                return Ok(Outcome::Timeout);
+            }
+
+            thread::sleep(POLL_INTERVAL);
+        }
+    }
+
+    pub fn kill(&mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+
+        if let Some(reader) = self.reader.take() {
+            let _ = reader.join();
+        }
+    }
+}
+
+impl Drop for Machine {
+    fn drop(&mut self) {
+        if self.outcome.is_none() {
+            self.kill();
+        }
+
+        let _ = fs::remove_file(&self.vars);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn outcome_from_exit_code() {
+        assert_eq!(Outcome::from_code(Some(0)), Outcome::PoweredOff);
+        assert_eq!(Outcome::from_code(Some(1)), Outcome::Failed(Some(1)));
+        assert_eq!(Outcome::from_code(Some(3)), Outcome::Exited(0));
+        assert_eq!(Outcome::from_code(Some(9)), Outcome::Exited(3));
+        assert_eq!(Outcome::from_code(Some(0xff)), Outcome::Exited(STOP_EXIT_STATUS));
+        assert_eq!(Outcome::from_code(Some(2)), Outcome::Failed(Some(2)));
+        assert_eq!(Outcome::from_code(None), Outcome::Failed(None));
+    }
+}
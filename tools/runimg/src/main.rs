@@ -0,0 +1,113 @@
+use std::env;
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+use std::time::Duration;
+use runimg::{build, Machine, Options, Outcome, STOP_EXIT_STATUS};
+
+const USAGE: &str = "usage: runimg [--no-build] [--timeout <seconds>] [--image <disk.img>] [--firmware <directory>]
+              [--memory <size>] [--log <file>] [--tftp <directory>] [--quiet] [-- <qemu arguments>...]
+
+Boots the system image headless in QEMU with the serial console attached to
+the terminal. Use --timeout 0 to disable the timeout. --tftp attaches user-mode
+networking whose TFTP server serves the directory, e.g. ./esp for tftp: boot
+entries.
+Exits with the status passed by the guest to the debug exit port, 124 on
+timeout and 125 if QEMU failed. The port is announced to the guest in an SMBIOS
+OEM string; without it the guest never writes to the port.";
+
+const DEFAULT_TIMEOUT: u64 = 120;
+
+struct Arguments {
+    build: bool,
+    timeout: Option<Duration>,
+    options: Options
+}
+
+fn parse_arguments(args: &[String]) -> Result<Arguments, String> {
+    let mut parsed = Arguments {
+        build: true,
+        timeout: Some(Duration::from_secs(DEFAULT_TIMEOUT)),
+        options: Options { interactive: true, ..Options::default() }
+    };
+    let mut args = args.iter();
+
+    while let Some(arg) = args.next() {
+        let (name, inline) = match arg.split_once('=') {
+            Some((name, value)) => (name, Some(value.to_string())),
+            None => (arg.as_str(), None)
+        };
+
+        let mut value = |name: &str| inline.clone()
+            .or_else(|| args.next().cloned())
+            .ok_or(format!("missing value for {name}"));
+
+        match name {
+            "--no-build" => parsed.build = false,
+            "--quiet" => parsed.options.echo = false,
+            "--timeout" => {
+                let timeout = value(name)?;
+                parsed.timeout = match timeout.parse() {
+                    Ok(0) => None,
+                    Ok(seconds) => Some(Duration::from_secs(seconds)),
+                    Err(_) => return Err(format!("invalid timeout {timeout}"))
+                };
+            },
+            "--image" => parsed.options.image = PathBuf::from(value(name)?),
+            "--firmware" => parsed.options.firmware = PathBuf::from(value(name)?),
+            "--memory" => parsed.options.memory = value(name)?,
+            "--log" => parsed.options.log = Some(PathBuf::from(value(name)?)),
+            "--tftp" => parsed.options.tftp = Some(PathBuf::from(value(name)?)),
+            "--" => {
+                parsed.options.extra = args.cloned().collect();
+                break;
+            },
+            _ => return Err(format!("unknown argument {arg}"))
+        }
+    }
+
+    Ok(parsed)
+}
+
+fn main() -> ExitCode {
+    let args: Vec<String> = env::args().skip(1).collect();
+
+    let args = match parse_arguments(&args) {
+        Ok(args) => args,
+        Err(e) => {
+            eprintln!("error: {e}\n{USAGE}");
+            return ExitCode::from(2);
+        }
+    };
+
+    if args.build {
+        if let Err(e) = build(Path::new(".")) {
+            eprintln!("error: {e}");
+            return ExitCode::FAILURE;
+        }
+    }
+
+    let outcome = Machine::start(&args.options).and_then(|mut machine| machine.wait(args.timeout));
+
+    match outcome {
+        Ok(outcome) => {
+            match outcome {
+                Outcome::Exited(STOP_EXIT_STATUS) => eprintln!("\nrunimg: the system stopped with a fatal error"),
+                Outcome::Exited(status) => eprintln!("\nrunimg: the system exited with status {status}"),
+                Outcome::PoweredOff => eprintln!("\nrunimg: the system powered off"),
+                Outcome::Failed(Some(code)) => eprintln!("\nrunimg: QEMU exited with code {code}"),
+                Outcome::Failed(None) => eprintln!("\nrunimg: QEMU was terminated"),
+                Outcome::Timeout => eprintln!("\nrunimg: timed out after {} seconds", args.timeout.unwrap_or_default().as_secs())
+            }
+
+            if let Some(log) = &args.options.log {
+                eprintln!("runimg: serial output saved to {}", log.display());
+            }
+
+            ExitCode::from(outcome.exit_code())
+        },
+        Err(e) => {
+            eprintln!("error: {e}");
+            ExitCode::from(Outcome::Failed(None).exit_code())
+        }
+    }
+}
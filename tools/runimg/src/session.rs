@@ -0,0 +1,138 @@
+use std::thread;
+use std::time::{Duration, Instant};
+use crate::{Error, Machine, Options, Outcome, POLL_INTERVAL};
+
+const DEFAULT_TIMEOUT: Duration = Duration::from_secs(60);
+const ESCAPE: char = '\x1b';
+
+pub struct Session {
+    machine: Machine,
+    cursor: usize,
+    cwd: String,
+    pub timeout: Duration
+}
+
+fn strip_escapes(text: &str) -> String {
+    let mut output = String::with_capacity(text.len());
+    let mut chars = text.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        if c != ESCAPE {
+            output.push(c);
+            continue;
+        }
+
+        match chars.next() {
+            Some('[') => {
+                for c in chars.by_ref() {
+                    if ('@'..='~').contains(&c) {
+                        break;
+                    }
+                }
+            },
+            Some('(' | ')') => {
+                chars.next();
+            },
+            _ => ()
+        }
+    }
+
+    output
+}
+
+fn prompt(text: &str) -> Option<(usize, String)> {
+    let text = text.strip_suffix("> ")?;
+    let start = text.rfind("\r\n")?;
+    let cwd = &text[start + 2..];
+
+    if cwd.starts_with('/') && !cwd.contains(['\r', '\n']) {
+        Some((start, cwd.to_string()))
+    } else {
+        None
+    }
+}
+
+impl Session {
+    pub fn start(options: &Options) -> Result<Self, Error> {
+        Ok(Self {
+            machine: Machine::start(options)?,
+            cursor: 0,
+            cwd: String::new(),
+            timeout: DEFAULT_TIMEOUT
+        })
+    }
+
+    pub fn boot(options: &Options) -> Result<Self, Error> {
+        let mut session = Self::start(options)?;
+        session.wait_for_prompt()?;
+        Ok(session)
+    }
+
+    pub fn cwd(&self) -> &str {
+        &self.cwd
+    }
+
+    pub fn output(&self) -> String {
+        strip_escapes(&String::from_utf8_lossy(&self.machine.output()))
+    }
+
+    fn pending(&self) -> String {
+        self.output().get(self.cursor..).unwrap_or_default().to_string()
+    }
+
+    fn wait_until<T>(&mut self, what: &str, mut check: impl FnMut(&str) -> Option<T>) -> Result<T, Error> {
+        let start = Instant::now();
+
+        loop {
+            let pending = self.pending();
+
+            if let Some(result) = check(&pending) {
+                return Ok(result);
+            }
+
+            if let Some(outcome) = self.machine.poll()? {
+                return Err(Error::Exited(outcome, what.to_string(), pending));
+            }
+
+            if start.elapsed() >= self.timeout {
+                return Err(Error::Timeout(what.to_string(), pending));
+            }
+
+            thread::sleep(POLL_INTERVAL);
+        }
+    }
+
+    pub fn expect(&mut self, text: &str) -> Result<String, Error> {
+        let (output, end) = self.wait_until(&format!("\"{text}\""), |pending| {
+            pending.find(text).map(|i| (pending[..i].to_string(), i + text.len()))
+        })?;
+
+        self.cursor += end;
+        Ok(output)
+    }
+
+    pub fn wait_for_prompt(&mut self) -> Result<String, Error> {
+        let (output, cwd, end) = self.wait_until("the prompt", |pending| {
+            prompt(pending).map(|(start, cwd)| (pending[..start].to_string(), cwd, pending.len()))
+        })?;
+
+        self.cursor += end;
+        self.cwd = cwd;
+        Ok(output)
+    }
+
+    pub fn type_line(&mut self, line: &str) -> Result<(), Error> {
+        self.machine.send(format!("{line}\r").as_bytes())
+    }
+
+    pub fn run(&mut self, command: &str) -> Result<String, Error> {
+        self.type_line(command)?;
+        self.expect(&format!("{command}\r\n"))?;
+        self.wait_for_prompt()
+    }
+
+    pub fn wait(&mut self) -> Result<Outcome, Error> {
+        let timeout = self.timeout;
+        self.machine.wait(Some(timeout))
+    }
+}
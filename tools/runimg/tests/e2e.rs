@@ -0,0 +1,176 @@
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use runimg::{Options, Outcome, Session, STOP_EXIT_STATUS};
+
+fn root() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("../..")
+}
+
+fn options(name: &str) -> Options {
+    let logs = Path::new(env!("CARGO_TARGET_TMPDIR")).join("e2e");
+    fs::create_dir_all(&logs).unwrap();
+
+    Options {
+        image: root().join("russet.img"),
+        firmware: root().join("firmware"),
+        log: Some(logs.join(format!("{name}.log"))),
+        echo: false,
+        snapshot: true,
+        ..Options::default()
+    }
+}
+
+fn missing_prerequisite(options: &Options) -> Option<String> {
+    let qemu = Command::new(&options.qemu).arg("--version").stdout(Stdio::null()).stderr(Stdio::null()).status();
+
+    if !options.image.is_file() {
+        Some(format!("{} not found, run ./build.sh first", options.image.display()))
+    } else if !options.firmware.join("OVMF_CODE.fd").is_file() {
+        Some(format!("no OVMF firmware in {}", options.firmware.display()))
+    } else if !qemu.is_ok_and(|status| status.success()) {
+        Some(format!("{} is not available", options.qemu))
+    } else if options.tftp.as_deref().is_some_and(|root| !root.is_dir()) {
+        Some(String::from("esp not found, run ./build.sh first"))
+    } else {
+        None
+    }
+}
+
+// Tests that boot the real image skip themselves when the image, firmware or
+// QEMU is missing. RUSSET_E2E=1 turns a missing prerequisite into a failure.
+fn skip(options: &Options) -> bool {
+    let Some(reason) = missing_prerequisite(options) else {
+        return false;
+    };
+
+    if env::var("RUSSET_E2E").is_ok_and(|value| value == "1") {
+        panic!("{reason}");
+    }
+
+    eprintln!("skipping: {reason}");
+    true
+}
+
+fn boot(name: &str) -> Option<Session> {
+    let options = options(name);
+    if skip(&options) {
+        return None;
+    }
+
+    Some(Session::boot(&options).unwrap_or_else(|e| panic!("{e}")))
+}
+
+fn run(session: &mut Session, command: &str) -> String {
+    session.run(command).unwrap_or_else(|e| panic!("{command}: {e}"))
+}
+
+// Exercises the session plumbing against tests/fake-guest.sh, not the system.
+#[test]
+fn harness() {
+    let guest = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fake-guest.sh");
+    let options = Options {
+        qemu: guest.display().to_string(),
+        image: guest,
+        ..options("harness")
+    };
+
+    let mut session = Session::boot(&options).unwrap();
+    assert_eq!(session.cwd(), "/User");
+    assert_eq!(run(&mut session, "Print Hello"), "Hello\r\n");
+    run(&mut session, "ChangeDirectory /System");
+    assert_eq!(session.cwd(), "/System");
+
+    session.type_line("Shutdown 3").unwrap();
+    assert_eq!(session.wait().unwrap(), Outcome::Exited(3));
+}
+
+#[test]
+fn change_directory() {
+    let Some(mut session) = boot("change_directory") else {
+        return;
+    };
+    assert_eq!(session.cwd(), "/User");
+
+    run(&mut session, "ChangeDirectory /System");
+    assert_eq!(session.cwd(), "/System");
+
+    run(&mut session, "ChangeDirectory Programs");
+    assert_eq!(session.cwd(), "/System/Programs");
+
+    assert!(run(&mut session, "ChangeDirectory Missing").contains("The file \"Missing\" could not be found."));
+    assert!(run(&mut session, "ChangeDirectory /System/Kernel").contains("The path \"/System/Kernel\" is not a valid directory."));
+    assert_eq!(session.cwd(), "/System/Programs");
+
+    run(&mut session, "ChangeDirectory");
+    assert_eq!(session.cwd(), "/User");
+}
+
+#[test]
+fn get_command_file() {
+    let Some(mut session) = boot("get_command_file") else {
+        return;
+    };
+
+    assert_eq!(run(&mut session, "GetCommandFile CommandInterpreter").trim(), "/System/Programs/CommandInterpreter");
+    assert!(run(&mut session, "GetCommandFile Missing").contains("The command \"Missing\" could not found."));
+}
+
+#[test]
+fn program_launch_errors() {
+    let Some(mut session) = boot("program_launch_errors") else {
+        return;
+    };
+
+    assert!(run(&mut session, "Missing").contains("\"Missing\" is not recognized as a valid internal command or external executable program."));
+    assert!(run(&mut session, "/System/Missing").contains("The file \"/System/Missing\" could not be found."));
+    assert!(run(&mut session, "/System/Kernel").contains("\"/System/Kernel\" is not a valid BunnyOS program."));
+}
+
+#[test]
+fn stop_screen() {
+    let Some(mut session) = boot("stop_screen") else {
+        return;
+    };
+
+    session.type_line("_Crash").unwrap();
+    session.expect("*** STOP: ").unwrap();
+    session.expect("SET_OF_INVALID_CONTEXT").unwrap();
+    session.expect("Please restart the system.").unwrap();
+    assert_eq!(session.wait().unwrap(), Outcome::Exited(STOP_EXIT_STATUS));
+}
+
+#[test]
+fn system_information() {
+    let Some(mut session) = boot("system_information") else {
+        return;
+    };
+
+    let output = run(&mut session, "GetSystemInformation");
+    assert!(output.contains("Kernel:"));
+    assert!(output.contains("Processor:"));
+    assert!(output.contains("Memory:"));
+
+    assert!(run(&mut session, "GetSystemLog --source=velm").contains("starting /System/Init"));
+    assert!(run(&mut session, "GetSystemLog --level=loud").contains("The level must be one of"));
+}
+
+#[test]
+fn network_boot() {
+    let options = Options { tftp: Some(root().join("esp")), ..options("network_boot") };
+    if skip(&options) {
+        return;
+    }
+
+    let mut session = Session::start(&options).unwrap();
+
+    session.expect("Press E to edit").unwrap();
+    session.type_line("c").unwrap();
+    session.expect("Rouse> ").unwrap();
+    session.type_line("tftp:/System/Kernel").unwrap();
+    session.expect("Loading /System/Kernel from 10.0.2.2").unwrap();
+    session.wait_for_prompt().unwrap();
+
+    assert!(run(&mut session, "GetBootTimeline").contains("/System/Init read"));
+}
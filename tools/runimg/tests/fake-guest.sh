@@ -0,0 +1,13 @@
+#!/bin/bash
+# Stands in for qemu-system-x86_64: a minimal command interpreter on stdio.
+cwd=/User
+printf '\033[2J\033[01;01Hrouse bootloader\r\n\r\n%s> ' "$cwd"
+while IFS= read -r -d $'\r' line; do
+    printf '%s\r\n' "$line"
+    case "$line" in
+        "ChangeDirectory "*) cwd=${line#ChangeDirectory } ;;
+        "Print "*) printf '\033[37m%s\033[0m\r\n' "${line#Print }" ;;
+        "Shutdown "*) exit $(( ((${line#Shutdown } + 1) << 1) | 1 )) ;;
+    esac
+    printf '\r\n%s> ' "$cwd"
+done